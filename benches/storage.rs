@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use entity_grid::prelude::*;
+
+const SIZE: i32 = 128;
+
+/// Fill every other cell of a `SIZE` by `SIZE` square
+fn fill<S: GridStorage>(grid: &mut Grid<S>) {
+    for x in 0..SIZE {
+        for y in 0..SIZE {
+            if (x + y) % 2 == 0 {
+                grid.insert(GridPosition::new(x, y), Entity::PLACEHOLDER, Rotation::Up);
+            }
+        }
+    }
+}
+
+fn grids() -> (Grid<HashMapStorage>, Grid, BoundedGrid) {
+    (
+        Grid::with_storage(HashMapStorage::default()),
+        Grid::new(),
+        BoundedGrid::bounded(GridPosition::new(0, 0), SIZE as u32, SIZE as u32).unwrap(),
+    )
+}
+
+fn bench_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert");
+    let (hash_map, chunked, bounded) = grids();
+    group.bench_function("hash_map", |b| b.iter(|| fill(&mut hash_map.clone())));
+    group.bench_function("chunked", |b| b.iter(|| fill(&mut chunked.clone())));
+    group.bench_function("bounded", |b| b.iter(|| fill(&mut bounded.clone())));
+    group.finish();
+}
+
+fn lookup_all<S: GridStorage>(grid: &Grid<S>) -> usize {
+    let mut found = 0;
+    for x in 0..SIZE {
+        for y in 0..SIZE {
+            if grid.contains(GridPosition::new(x, y)) {
+                found += 1;
+            }
+        }
+    }
+    found
+}
+
+fn bench_get(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get");
+    let (mut hash_map, mut chunked, mut bounded) = grids();
+    fill(&mut hash_map);
+    fill(&mut chunked);
+    fill(&mut bounded);
+    group.bench_function("hash_map", |b| b.iter(|| lookup_all(&hash_map)));
+    group.bench_function("chunked", |b| b.iter(|| lookup_all(&chunked)));
+    group.bench_function("bounded", |b| b.iter(|| lookup_all(&bounded)));
+    group.finish();
+}
+
+fn bench_radius(c: &mut Criterion) {
+    let mut group = c.benchmark_group("radius");
+    let (mut hash_map, mut chunked, mut bounded) = grids();
+    fill(&mut hash_map);
+    fill(&mut chunked);
+    fill(&mut bounded);
+    let centre = GridPosition::new(SIZE / 2, SIZE / 2);
+    for radius in [4, 16] {
+        let query = RadiusQuery::circle(radius);
+        group.bench_with_input(BenchmarkId::new("hash_map", radius), &query, |b, query| {
+            b.iter(|| hash_map.iter_radius_neighbors(centre, *query).count())
+        });
+        group.bench_with_input(BenchmarkId::new("chunked", radius), &query, |b, query| {
+            b.iter(|| chunked.iter_radius_neighbors(centre, *query).count())
+        });
+        group.bench_with_input(BenchmarkId::new("bounded", radius), &query, |b, query| {
+            b.iter(|| bounded.iter_radius_neighbors(centre, *query).count())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_insert, bench_get, bench_radius);
+criterion_main!(benches);
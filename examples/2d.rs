@@ -14,9 +14,14 @@ fn main() {
             settings: EntityGridSettings {
                 up_offset: 0.0,
                 cell_size: 256.,
+                plane: GridPlane::XY,
+                ..default()
             },
         })
-        .add_plugins((RemoteHttpPlugin::default(), RemotePlugin::default()))
+        .add_plugins((
+            RemoteHttpPlugin::default(),
+            RemotePlugin::default().with_entity_grid_methods(),
+        ))
         .add_systems(Startup, setup)
         .add_systems(Update, update)
         .run();
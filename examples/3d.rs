@@ -19,9 +19,13 @@ fn main() {
             settings: EntityGridSettings {
                 up_offset: 0.0,
                 cell_size: 0.5,
+                ..default()
             },
         })
-        .add_plugins((RemoteHttpPlugin::default(), RemotePlugin::default()))
+        .add_plugins((
+            RemoteHttpPlugin::default(),
+            RemotePlugin::default().with_entity_grid_methods(),
+        ))
         .add_systems(Startup, setup)
         .run();
 }
@@ -0,0 +1,145 @@
+use bevy::{prelude::*, utils::HashMap};
+
+pub mod prelude {
+    pub use super::CellBitmap;
+}
+
+use crate::prelude::*;
+
+/// The number of words holding the bits of a chunk
+const WORDS: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize / 64;
+
+/// A set of cells stored as one bit per cell, chunk by chunk
+#[derive(Debug, Default, Clone, PartialEq, Eq, Reflect)]
+pub struct CellBitmap {
+    chunks: HashMap<ChunkPosition, [u64; WORDS]>,
+}
+
+impl CellBitmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the word and bit of a cell in its chunk
+    fn locate(position: GridPosition) -> (ChunkPosition, usize, u64) {
+        let chunk = ChunkPosition::of(position);
+        let origin = chunk.origin();
+        let index = ((position.y - origin.y) * CHUNK_SIZE + position.x - origin.x) as usize;
+        (chunk, index / 64, 1 << (index % 64))
+    }
+
+    /// Add a cell, returning whether it was missing
+    pub fn insert(&mut self, position: GridPosition) -> bool {
+        let (chunk, word, bit) = Self::locate(position);
+        let words = self.chunks.entry(chunk).or_insert([0; WORDS]);
+        let missing = words[word] & bit == 0;
+        words[word] |= bit;
+        missing
+    }
+
+    /// Remove a cell, returning whether it was present
+    pub fn remove(&mut self, position: GridPosition) -> bool {
+        let (chunk, word, bit) = Self::locate(position);
+        let Some(words) = self.chunks.get_mut(&chunk) else {
+            return false;
+        };
+        let present = words[word] & bit != 0;
+        words[word] &= !bit;
+        if words.iter().all(|word| *word == 0) {
+            self.chunks.remove(&chunk);
+        }
+        present
+    }
+
+    pub fn contains(&self, position: GridPosition) -> bool {
+        let (chunk, word, bit) = Self::locate(position);
+        self.chunks
+            .get(&chunk)
+            .is_some_and(|words| words[word] & bit != 0)
+    }
+
+    /// Add every cell of another bitmap
+    pub fn union_with(&mut self, other: &Self) {
+        for (chunk, other_words) in &other.chunks {
+            let words = self.chunks.entry(*chunk).or_insert([0; WORDS]);
+            for (word, other_word) in words.iter_mut().zip(other_words) {
+                *word |= other_word;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks
+            .values()
+            .flatten()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Iterate over the cells, chunk by chunk
+    pub fn iter(&self) -> impl Iterator<Item = GridPosition> + '_ {
+        self.chunks.iter().flat_map(|(chunk, words)| {
+            let origin = chunk.origin();
+            (0..WORDS * 64)
+                .filter(|index| words[index / 64] & (1 << (index % 64)) != 0)
+                .map(move |index| {
+                    let index = index as i32;
+                    origin + IVec2::new(index % CHUNK_SIZE, index / CHUNK_SIZE)
+                })
+        })
+    }
+}
+
+impl FromIterator<GridPosition> for CellBitmap {
+    fn from_iter<T: IntoIterator<Item = GridPosition>>(iter: T) -> Self {
+        let mut bitmap = Self::new();
+        bitmap.extend(iter);
+        bitmap
+    }
+}
+
+impl Extend<GridPosition> for CellBitmap {
+    fn extend<T: IntoIterator<Item = GridPosition>>(&mut self, iter: T) {
+        for position in iter {
+            self.insert(position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_bitmap() {
+        let mut bitmap = CellBitmap::new();
+        assert!(bitmap.insert(GridPosition::new(-1, 5)));
+        assert!(!bitmap.insert(GridPosition::new(-1, 5)));
+        bitmap.insert(GridPosition::new(CHUNK_SIZE + 3, 0));
+        assert_eq!(bitmap.len(), 2);
+        assert!(bitmap.contains(GridPosition::new(-1, 5)));
+        assert!(!bitmap.contains(GridPosition::new(5, -1)));
+
+        let other = CellBitmap::from_iter([GridPosition::new(0, 0), GridPosition::new(-1, 5)]);
+        bitmap.union_with(&other);
+        let mut cells = bitmap.iter().collect::<Vec<_>>();
+        cells.sort_by_key(|cell| (cell.x, cell.y));
+        assert_eq!(cells, vec![
+            GridPosition::new(-1, 5),
+            GridPosition::new(0, 0),
+            GridPosition::new(CHUNK_SIZE + 3, 0),
+        ]);
+
+        assert!(bitmap.remove(GridPosition::new(CHUNK_SIZE + 3, 0)));
+        assert!(!bitmap.remove(GridPosition::new(CHUNK_SIZE + 3, 0)));
+        assert_eq!(bitmap.len(), 2);
+    }
+}
@@ -0,0 +1,269 @@
+use bevy::prelude::*;
+
+pub mod prelude {
+    pub use super::{BoundedGrid, BoundedStorage};
+}
+
+use crate::prelude::*;
+
+/// A fixed-size grid, such as an inventory or a puzzle board
+///
+/// Bounded grids are used on their own: the grids of the plugin are always chunked, use
+/// `PlacementRule::within` to keep their entities in a rectangle.
+pub type BoundedGrid = Grid<BoundedStorage>;
+
+/// Stores a fixed rectangle of cells in a flat `Vec`, row by row
+///
+/// Cells outside of the rectangle are rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Reflect)]
+pub struct BoundedStorage {
+    origin: GridPosition,
+    width: i32,
+    height: i32,
+    cells: Vec<Option<GridEntity>>,
+    len: usize,
+}
+
+impl BoundedStorage {
+    /// Create an empty rectangle of cells starting at `origin`
+    ///
+    /// `None` if a side is empty or the cells don't all have `i32` coordinates and indices.
+    pub fn new(origin: GridPosition, width: u32, height: u32) -> Option<Self> {
+        let width = i32::try_from(width).ok().filter(|width| *width > 0)?;
+        let height = i32::try_from(height).ok().filter(|height| *height > 0)?;
+        let cells = width.checked_mul(height)?;
+        origin.x.checked_add(width - 1)?;
+        origin.y.checked_add(height - 1)?;
+        Some(Self {
+            origin,
+            width,
+            height,
+            cells: vec![None; cells as usize],
+            len: 0,
+        })
+    }
+
+    fn index(&self, position: GridPosition) -> Option<usize> {
+        let x = position.x - self.origin.x;
+        let y = position.y - self.origin.y;
+        ((0..self.width).contains(&x) && (0..self.height).contains(&y))
+            .then(|| (y * self.width + x) as usize)
+    }
+
+    fn position(&self, index: usize) -> GridPosition {
+        let index = index as i32;
+        self.origin + IVec2::new(index % self.width, index / self.width)
+    }
+}
+
+impl GridStorage for BoundedStorage {
+    fn bounds(&self) -> Option<(GridPosition, GridPosition)> {
+        let max = self.origin + IVec2::new(self.width - 1, self.height - 1);
+        Some((self.origin, max))
+    }
+
+    fn in_bounds(&self, position: GridPosition) -> bool {
+        self.index(position).is_some()
+    }
+
+    fn get(&self, position: GridPosition) -> Option<GridEntity> {
+        self.cells[self.index(position)?]
+    }
+
+    fn get_mut(&mut self, position: GridPosition) -> Option<&mut GridEntity> {
+        let index = self.index(position)?;
+        self.cells[index].as_mut()
+    }
+
+    fn insert(
+        &mut self,
+        position: GridPosition,
+        entry: GridEntity,
+    ) -> Result<Option<GridEntity>, GridOutOfBounds> {
+        let index = self.index(position).ok_or(GridOutOfBounds(position))?;
+        let previous = self.cells[index].replace(entry);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        Ok(previous)
+    }
+
+    fn remove(&mut self, position: GridPosition) -> Option<GridEntity> {
+        let index = self.index(position)?;
+        let removed = self.cells[index].take();
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (GridPosition, &GridEntity)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| entry.as_ref().map(|entry| (self.position(index), entry)))
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = (GridPosition, &mut GridEntity)> {
+        let (origin, width) = (self.origin, self.width);
+        self.cells
+            .iter_mut()
+            .enumerate()
+            .filter_map(move |(index, entry)| {
+                let index = index as i32;
+                let position = origin + IVec2::new(index % width, index / width);
+                entry.as_mut().map(|entry| (position, entry))
+            })
+    }
+}
+
+impl Grid<BoundedStorage> {
+    /// Create an empty grid of `width` by `height` cells starting at `origin`
+    ///
+    /// `None` if the rectangle is empty or too large, see `BoundedStorage::new`.
+    pub fn bounded(origin: GridPosition, width: u32, height: u32) -> Option<Self> {
+        BoundedStorage::new(origin, width, height).map(Self::with_storage)
+    }
+
+    /// Get the cell with the lowest coordinates
+    pub fn origin(&self) -> GridPosition {
+        self.storage().origin
+    }
+
+    pub fn width(&self) -> u32 {
+        self.storage().width as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.storage().height as u32
+    }
+
+    /// Iterate over every cell of a row, empty or not, from left to right
+    pub fn row(&self, y: i32) -> impl Iterator<Item = (GridPosition, Option<GridEntity>)> + '_ {
+        let storage = self.storage();
+        let cells = if (0..storage.height).contains(&(y - storage.origin.y)) {
+            0..storage.width
+        } else {
+            0..0
+        };
+        cells.map(move |x| {
+            let position = GridPosition::new(storage.origin.x + x, y);
+            (position, storage.get(position))
+        })
+    }
+
+    /// Iterate over every cell of a column, empty or not, from bottom to top
+    pub fn column(&self, x: i32) -> impl Iterator<Item = (GridPosition, Option<GridEntity>)> + '_ {
+        let storage = self.storage();
+        let cells = if (0..storage.width).contains(&(x - storage.origin.x)) {
+            0..storage.height
+        } else {
+            0..0
+        };
+        cells.map(move |y| {
+            let position = GridPosition::new(x, storage.origin.y + y);
+            (position, storage.get(position))
+        })
+    }
+
+    /// Iterate over the rows, from bottom to top
+    pub fn rows(
+        &self,
+    ) -> impl Iterator<Item = impl Iterator<Item = (GridPosition, Option<GridEntity>)> + '_> + '_
+    {
+        let origin = self.origin().y;
+        (origin..origin + self.height() as i32).map(|y| self.row(y))
+    }
+
+    /// Iterate over the columns, from left to right
+    pub fn columns(
+        &self,
+    ) -> impl Iterator<Item = impl Iterator<Item = (GridPosition, Option<GridEntity>)> + '_> + '_
+    {
+        let origin = self.origin().x;
+        (origin..origin + self.width() as i32).map(|x| self.column(x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounded_grid() {
+        let mut grid = BoundedGrid::bounded(GridPosition::new(-1, -1), 3, 2).unwrap();
+        let entity = Entity::PLACEHOLDER;
+        let rotation = Rotation::default();
+
+        assert_eq!(
+            grid.try_insert(GridPosition::new(1, 0), entity, rotation),
+            Ok(None)
+        );
+        assert_eq!(
+            grid.try_insert(GridPosition::new(2, 0), entity, rotation),
+            Err(GridOutOfBounds(GridPosition::new(2, 0)))
+        );
+        // Out of bounds inserts are ignored
+        grid.insert(GridPosition::new(-1, 1), entity, rotation);
+        assert_eq!(grid.len(), 1);
+        assert!(grid.contains(GridPosition::new(1, 0)));
+        assert_eq!(
+            grid.iter()
+                .map(|(position, _)| position)
+                .collect::<Vec<_>>(),
+            vec![GridPosition::new(1, 0)]
+        );
+        assert_eq!(
+            grid.storage().bounds(),
+            Some((GridPosition::new(-1, -1), GridPosition::new(1, 0)))
+        );
+    }
+
+    #[test]
+    fn test_bounded_rows_and_columns() {
+        let mut grid = BoundedGrid::bounded(GridPosition::new(0, 0), 3, 2).unwrap();
+        grid.insert(GridPosition::new(2, 1), Entity::PLACEHOLDER, Rotation::Up);
+
+        let row = grid.row(1).collect::<Vec<_>>();
+        assert_eq!(row.len(), 3);
+        assert_eq!(row[0], (GridPosition::new(0, 1), None));
+        assert!(row[2].1.is_some());
+        assert_eq!(grid.row(2).count(), 0);
+
+        assert_eq!(
+            grid.column(2).filter(|(_, entry)| entry.is_some()).count(),
+            1
+        );
+        assert_eq!(grid.rows().count(), 2);
+        assert_eq!(grid.columns().map(Iterator::count).sum::<usize>(), 6);
+    }
+
+    #[test]
+    fn test_bounded_nearest_free() {
+        let mut grid = BoundedGrid::bounded(GridPosition::new(0, 0), 2, 1).unwrap();
+        grid.insert(GridPosition::new(0, 0), Entity::PLACEHOLDER, Rotation::Up);
+        // The free cells below the board are out of bounds
+        assert_eq!(
+            grid.find_nearest_free(GridPosition::new(0, 0)),
+            Some(GridPosition::new(1, 0))
+        );
+        grid.insert(GridPosition::new(1, 0), Entity::PLACEHOLDER, Rotation::Up);
+        assert_eq!(grid.find_nearest_free(GridPosition::new(0, 0)), None);
+    }
+
+    #[test]
+    fn test_bounded_size() {
+        let origin = GridPosition::new(0, 0);
+        assert!(BoundedGrid::bounded(origin, 0, 2).is_none());
+        assert!(BoundedGrid::bounded(origin, 2, 0).is_none());
+        assert!(BoundedGrid::bounded(origin, u32::MAX, 1).is_none());
+        assert!(BoundedGrid::bounded(origin, 1 << 16, 1 << 16).is_none());
+        // The last cell must have a coordinate
+        assert!(BoundedGrid::bounded(GridPosition::new(i32::MAX, 0), 2, 1).is_none());
+        assert!(BoundedGrid::bounded(GridPosition::new(i32::MAX, 0), 1, 1).is_some());
+    }
+}
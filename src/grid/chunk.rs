@@ -0,0 +1,341 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+pub mod prelude {
+    pub use super::{CHUNK_SIZE, ChunkPosition, ChunkedStorage, GridChunk};
+}
+
+use crate::prelude::*;
+
+/// The width and height of a chunk, in cells
+pub const CHUNK_SIZE: i32 = 16;
+
+/// The number of cells in a chunk
+const CHUNK_AREA: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// The position of a chunk, in chunks
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Reflect,
+    Serialize,
+    Deserialize,
+)]
+pub struct ChunkPosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl ChunkPosition {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    /// Get the chunk containing a cell
+    pub fn of(position: GridPosition) -> Self {
+        Self {
+            x: position.x.div_euclid(CHUNK_SIZE),
+            y: position.y.div_euclid(CHUNK_SIZE),
+        }
+    }
+
+    /// Get the cell of the chunk with the lowest coordinates
+    pub fn origin(&self) -> GridPosition {
+        GridPosition::new(self.x * CHUNK_SIZE, self.y * CHUNK_SIZE)
+    }
+
+    /// Whether a cell is part of the chunk
+    pub fn contains(&self, position: GridPosition) -> bool {
+        Self::of(position) == *self
+    }
+
+    /// Iterate over the cells of the chunk, row by row
+    pub fn cells(&self) -> impl Iterator<Item = GridPosition> {
+        let origin = self.origin();
+        (0..CHUNK_SIZE).flat_map(move |y| (0..CHUNK_SIZE).map(move |x| origin + IVec2::new(x, y)))
+    }
+}
+
+/// A chunk of cells, stored densely
+#[derive(Debug, Clone, PartialEq, Eq, Reflect)]
+pub struct GridChunk {
+    position: ChunkPosition,
+    cells: Vec<Option<GridEntity>>,
+    len: usize,
+}
+
+impl GridChunk {
+    /// Create an empty chunk
+    pub fn new(position: ChunkPosition) -> Self {
+        Self {
+            position,
+            cells: vec![None; CHUNK_AREA],
+            len: 0,
+        }
+    }
+
+    /// Get the position of the chunk
+    pub fn position(&self) -> ChunkPosition {
+        self.position
+    }
+
+    /// Get the index of a cell, `None` if it isn't part of the chunk
+    fn index(&self, position: GridPosition) -> Option<usize> {
+        if !self.position.contains(position) {
+            return None;
+        }
+        let x = position.x.rem_euclid(CHUNK_SIZE);
+        let y = position.y.rem_euclid(CHUNK_SIZE);
+        Some((y * CHUNK_SIZE + x) as usize)
+    }
+
+    /// Get the entry of a cell, `None` if it is empty or outside the chunk
+    pub fn get(&self, position: GridPosition) -> Option<GridEntity> {
+        self.cells[self.index(position)?]
+    }
+
+    pub fn get_mut(&mut self, position: GridPosition) -> Option<&mut GridEntity> {
+        let index = self.index(position)?;
+        self.cells[index].as_mut()
+    }
+
+    /// Insert an entry, returning the previous one or an error if the cell is outside the chunk
+    pub fn insert(
+        &mut self,
+        position: GridPosition,
+        entry: GridEntity,
+    ) -> Result<Option<GridEntity>, GridOutOfBounds> {
+        let index = self.index(position).ok_or(GridOutOfBounds(position))?;
+        let previous = self.cells[index].replace(entry);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        Ok(previous)
+    }
+
+    pub fn remove(&mut self, position: GridPosition) -> Option<GridEntity> {
+        let index = self.index(position)?;
+        let removed = self.cells[index].take();
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// The number of occupied cells
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterate over the occupied cells, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (GridPosition, &GridEntity)> {
+        self.position
+            .cells()
+            .zip(&self.cells)
+            .filter_map(|(position, entry)| entry.as_ref().map(|entry| (position, entry)))
+    }
+
+    /// Iterate mutably over the occupied cells, row by row
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (GridPosition, &mut GridEntity)> {
+        self.position
+            .cells()
+            .zip(&mut self.cells)
+            .filter_map(|(position, entry)| entry.as_mut().map(|entry| (position, entry)))
+    }
+}
+
+/// Stores cells in dense chunks of `CHUNK_SIZE` cells, the default storage of a `Grid`
+///
+/// Chunks are created when a cell is first inserted and dropped once empty.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub struct ChunkedStorage {
+    chunks: HashMap<ChunkPosition, GridChunk>,
+    len: usize,
+}
+
+impl ChunkedStorage {
+    /// Get the chunk at a chunk position, `None` if none of its cells are occupied
+    pub fn chunk(&self, chunk: ChunkPosition) -> Option<&GridChunk> {
+        self.chunks.get(&chunk)
+    }
+
+    /// Iterate over the chunks with occupied cells
+    pub fn chunks(&self) -> impl Iterator<Item = &GridChunk> {
+        self.chunks.values()
+    }
+
+    /// Insert a whole chunk, returning the chunk it replaces
+    pub fn insert_chunk(&mut self, chunk: GridChunk) -> Option<GridChunk> {
+        if chunk.is_empty() {
+            return self.remove_chunk(chunk.position());
+        }
+        self.len += chunk.len();
+        let previous = self.chunks.insert(chunk.position(), chunk);
+        if let Some(previous) = &previous {
+            self.len -= previous.len();
+        }
+        previous
+    }
+
+    /// Remove a whole chunk
+    pub fn remove_chunk(&mut self, chunk: ChunkPosition) -> Option<GridChunk> {
+        let removed = self.chunks.remove(&chunk)?;
+        self.len -= removed.len();
+        Some(removed)
+    }
+}
+
+impl GridStorage for ChunkedStorage {
+    fn get(&self, position: GridPosition) -> Option<GridEntity> {
+        self.chunks.get(&ChunkPosition::of(position))?.get(position)
+    }
+
+    fn get_mut(&mut self, position: GridPosition) -> Option<&mut GridEntity> {
+        self.chunks
+            .get_mut(&ChunkPosition::of(position))?
+            .get_mut(position)
+    }
+
+    fn insert(
+        &mut self,
+        position: GridPosition,
+        entry: GridEntity,
+    ) -> Result<Option<GridEntity>, GridOutOfBounds> {
+        let previous = self
+            .chunks
+            .entry(ChunkPosition::of(position))
+            .or_insert_with_key(|chunk| GridChunk::new(*chunk))
+            .insert(position, entry)?;
+        if previous.is_none() {
+            self.len += 1;
+        }
+        Ok(previous)
+    }
+
+    fn remove(&mut self, position: GridPosition) -> Option<GridEntity> {
+        let key = ChunkPosition::of(position);
+        let chunk = self.chunks.get_mut(&key)?;
+        let removed = chunk.remove(position)?;
+        if chunk.is_empty() {
+            self.chunks.remove(&key);
+        }
+        self.len -= 1;
+        Some(removed)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (GridPosition, &GridEntity)> {
+        self.chunks.values().flat_map(GridChunk::iter)
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = (GridPosition, &mut GridEntity)> {
+        self.chunks.values_mut().flat_map(GridChunk::iter_mut)
+    }
+
+    /// Nearby cells mostly share a chunk, so the chunk of the previous lookup is kept
+    fn lookup(&self) -> impl FnMut(GridPosition) -> Option<GridEntity> {
+        let mut cached: Option<(ChunkPosition, Option<&GridChunk>)> = None;
+        move |position| {
+            let key = ChunkPosition::of(position);
+            let chunk = match cached {
+                Some((cached_key, chunk)) if cached_key == key => chunk,
+                _ => {
+                    let chunk = self.chunks.get(&key);
+                    cached = Some((key, chunk));
+                    chunk
+                }
+            };
+            chunk?.get(position)
+        }
+    }
+}
+
+impl Grid {
+    /// Get the chunk at a chunk position, `None` if none of its cells are occupied
+    pub fn chunk(&self, chunk: ChunkPosition) -> Option<&GridChunk> {
+        self.storage().chunk(chunk)
+    }
+
+    /// Iterate over the chunks with occupied cells
+    pub fn chunks(&self) -> impl Iterator<Item = &GridChunk> {
+        self.storage().chunks()
+    }
+
+    /// Insert a whole chunk, returning the chunk it replaces
+    pub fn insert_chunk(&mut self, chunk: GridChunk) -> Option<GridChunk> {
+        self.storage_mut().insert_chunk(chunk)
+    }
+
+    /// Remove a whole chunk
+    pub fn remove_chunk(&mut self, chunk: ChunkPosition) -> Option<GridChunk> {
+        self.storage_mut().remove_chunk(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_position() {
+        assert_eq!(
+            ChunkPosition::of(GridPosition::new(0, 15)),
+            ChunkPosition::new(0, 0)
+        );
+        assert_eq!(
+            ChunkPosition::of(GridPosition::new(-1, 16)),
+            ChunkPosition::new(-1, 1)
+        );
+        assert_eq!(
+            ChunkPosition::new(-1, 1).origin(),
+            GridPosition::new(-16, 16)
+        );
+        assert_eq!(ChunkPosition::new(2, -3).cells().count(), CHUNK_AREA);
+        assert!(
+            ChunkPosition::new(2, -3)
+                .cells()
+                .all(|cell| ChunkPosition::new(2, -3).contains(cell))
+        );
+    }
+
+    #[test]
+    fn test_chunk() {
+        let mut chunk = GridChunk::new(ChunkPosition::new(-1, 0));
+        let position = GridPosition::new(-3, 4);
+        assert_eq!(chunk.insert(position, GridEntity::test()), Ok(None));
+        assert_eq!(
+            chunk.insert(position, GridEntity::test()),
+            Ok(Some(GridEntity::test()))
+        );
+        // Cells of other chunks are rejected instead of aliasing a cell of this one
+        let outside = GridPosition::new(-3 + CHUNK_SIZE, 4);
+        assert_eq!(
+            chunk.insert(outside, GridEntity::test()),
+            Err(GridOutOfBounds(outside))
+        );
+        assert_eq!(chunk.get(outside), None);
+        assert_eq!(chunk.remove(outside), None);
+        assert_eq!(chunk.len(), 1);
+        assert_eq!(chunk.iter().collect::<Vec<_>>(), vec![(
+            position,
+            &GridEntity::test()
+        )]);
+        assert_eq!(chunk.remove(position), Some(GridEntity::test()));
+        assert!(chunk.is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub mod prelude {
+    pub use super::Direction8;
+}
+
+use crate::prelude::*;
+
+/// One of the four cardinal and four ordinal directions of a square grid
+///
+/// North is grid y, like `Rotation::Up`, and directions go clockwise.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum Direction8 {
+    #[default]
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction8 {
+    /// Every direction, clockwise from `North`
+    pub const ALL: [Self; 8] = [
+        Self::North,
+        Self::NorthEast,
+        Self::East,
+        Self::SouthEast,
+        Self::South,
+        Self::SouthWest,
+        Self::West,
+        Self::NorthWest,
+    ];
+
+    /// The directions sharing an edge, clockwise from `North`
+    pub const CARDINAL: [Self; 4] = [Self::North, Self::East, Self::South, Self::West];
+
+    /// The directions sharing a corner, clockwise from `NorthEast`
+    pub const ORDINAL: [Self; 4] = [
+        Self::NorthEast,
+        Self::SouthEast,
+        Self::SouthWest,
+        Self::NorthWest,
+    ];
+
+    /// Get the position of the direction in `ALL`
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn is_cardinal(&self) -> bool {
+        self.index().is_multiple_of(2)
+    }
+
+    pub fn is_ordinal(&self) -> bool {
+        !self.is_cardinal()
+    }
+
+    /// Get the offset to the neighbor in this direction
+    pub fn to_ivec2(&self) -> IVec2 {
+        match self {
+            Self::North => IVec2::new(0, 1),
+            Self::NorthEast => IVec2::new(1, 1),
+            Self::East => IVec2::new(1, 0),
+            Self::SouthEast => IVec2::new(1, -1),
+            Self::South => IVec2::new(0, -1),
+            Self::SouthWest => IVec2::new(-1, -1),
+            Self::West => IVec2::new(-1, 0),
+            Self::NorthWest => IVec2::new(-1, 1),
+        }
+    }
+
+    /// Get the direction closest to a direction vector, `None` for the zero vector
+    pub fn from_ivec2(direction: IVec2) -> Option<Self> {
+        if direction == IVec2::ZERO {
+            return None;
+        }
+        let angle = (direction.x as f32).atan2(direction.y as f32);
+        Some(Self::from_steps((angle / FRAC_PI_4).round() as i32))
+    }
+
+    /// Get the clockwise angle from grid y, like `Rotation::to_angle`
+    pub fn to_angle(&self) -> f32 {
+        self.index() as f32 * FRAC_PI_4
+    }
+
+    /// Get the direction after a number of clockwise eighth turns from `North`
+    pub fn from_steps(steps: i32) -> Self {
+        Self::ALL[steps.rem_euclid(8) as usize]
+    }
+
+    /// Turn clockwise by an eighth
+    pub fn next(&self) -> Self {
+        Self::from_steps(self.index() as i32 + 1)
+    }
+
+    /// Turn counterclockwise by an eighth
+    pub fn previous(&self) -> Self {
+        Self::from_steps(self.index() as i32 - 1)
+    }
+
+    pub fn opposite(&self) -> Self {
+        Self::from_steps(self.index() as i32 + 4)
+    }
+
+    /// Turn by a rotation, as if `North` was `Rotation::Up`
+    pub fn rotate(&self, rotation: Rotation) -> Self {
+        Self::from_steps(self.index() as i32 + rotation.quarter_turns() * 2)
+    }
+
+    /// Get the rotation facing the direction, `None` for ordinal directions
+    pub fn to_rotation(&self) -> Option<Rotation> {
+        self.is_cardinal()
+            .then(|| Rotation::from_quarter_turns(self.index() as i32 / 2))
+    }
+}
+
+impl From<Rotation> for Direction8 {
+    fn from(rotation: Rotation) -> Self {
+        Self::from_steps(rotation.quarter_turns() * 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_direction8() {
+        assert_eq!(
+            Direction8::from_ivec2(IVec2::new(5, 4)),
+            Some(Direction8::NorthEast)
+        );
+        assert_eq!(
+            Direction8::from_ivec2(IVec2::new(-5, 1)),
+            Some(Direction8::West)
+        );
+        assert_eq!(Direction8::from_ivec2(IVec2::ZERO), None);
+        for direction in Direction8::ALL {
+            assert_eq!(
+                Direction8::from_ivec2(direction.to_ivec2()),
+                Some(direction)
+            );
+            assert_eq!(direction.to_ivec2(), -direction.opposite().to_ivec2());
+            assert_eq!(direction.next().previous(), direction);
+        }
+        assert_eq!(
+            Direction8::NorthWest.rotate(Rotation::Right),
+            Direction8::NorthEast
+        );
+        assert_eq!(Direction8::from(Rotation::Left), Direction8::West);
+        assert_eq!(Direction8::South.to_rotation(), Some(Rotation::Down));
+        assert_eq!(Direction8::SouthEast.to_rotation(), None);
+        assert_eq!(Direction8::East.to_angle(), Rotation::Right.to_angle());
+    }
+}
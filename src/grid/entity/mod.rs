@@ -1,25 +1,41 @@
 pub mod neighbor;
 pub mod rotation;
+pub mod tags;
 
 use crate::prelude::*;
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 pub mod prelude {
     pub use super::GridEntity;
     pub use super::neighbor::prelude::*;
     pub use super::rotation::prelude::*;
+    pub use super::tags::prelude::*;
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
 pub struct GridEntity {
     pub entity: Entity,
     pub rotation: Rotation,
+    /// The tags of the entity, synced from its `GridTags`
+    #[serde(default)]
+    pub tags: GridTags,
 }
 
 impl GridEntity {
     pub fn new(entity: Entity, rotation: Rotation) -> Self {
-        Self { entity, rotation }
+        Self {
+            entity,
+            rotation,
+            tags: GridTags::NONE,
+        }
+    }
+
+    /// Get this entry with the given tags
+    pub fn with_tags(mut self, tags: GridTags) -> Self {
+        self.tags = tags;
+        self
     }
 
     #[cfg(test)]
@@ -27,6 +43,7 @@ impl GridEntity {
         Self {
             entity: Entity::PLACEHOLDER,
             rotation: Rotation::default(),
+            tags: GridTags::NONE,
         }
     }
 }
@@ -1,12 +1,16 @@
+use std::ops::Index;
+
 use bevy::prelude::*;
 
+use super::{NO_NEIGHBOR, dedup_neighbors};
 use crate::prelude::*;
 
 pub mod prelude {
     pub use super::CardinalNeighbors;
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Default)]
 pub struct CardinalNeighbors {
     pub north: Option<Neighbor>,
     pub east: Option<Neighbor>,
@@ -14,6 +18,12 @@ pub struct CardinalNeighbors {
     pub west: Option<Neighbor>,
 }
 
+impl Default for CardinalNeighbors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CardinalNeighbors {
     pub fn new() -> Self {
         Self {
@@ -26,52 +36,78 @@ impl CardinalNeighbors {
 
     pub fn with_empty_entities(&self) -> Self {
         CardinalNeighbors {
-            north: match self.north.clone() {
-                Some(entity) => Some(Neighbor {
-                    position: entity.position,
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: entity.entry.rotation,
-                    },
-                }),
-                None => None,
-            },
-            east: match self.east.clone() {
-                Some(entity) => Some(Neighbor {
-                    position: entity.position,
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: entity.entry.rotation,
-                    },
-                }),
-                None => None,
-            },
-            south: match self.south.clone() {
-                Some(entity) => Some(Neighbor {
-                    position: entity.position,
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: entity.entry.rotation,
-                    },
-                }),
-                None => None,
-            },
-            west: match self.west.clone() {
-                Some(entity) => Some(Neighbor {
-                    position: entity.position,
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: entity.entry.rotation,
-                    },
-                }),
-                None => None,
-            },
+            north: self.north.clone().map(|entity| Neighbor {
+                position: entity.position,
+                entry: GridEntity {
+                    entity: Entity::PLACEHOLDER,
+                    ..entity.entry
+                },
+            }),
+            east: self.east.clone().map(|entity| Neighbor {
+                position: entity.position,
+                entry: GridEntity {
+                    entity: Entity::PLACEHOLDER,
+                    ..entity.entry
+                },
+            }),
+            south: self.south.clone().map(|entity| Neighbor {
+                position: entity.position,
+                entry: GridEntity {
+                    entity: Entity::PLACEHOLDER,
+                    ..entity.entry
+                },
+            }),
+            west: self.west.clone().map(|entity| Neighbor {
+                position: entity.position,
+                entry: GridEntity {
+                    entity: Entity::PLACEHOLDER,
+                    ..entity.entry
+                },
+            }),
         }
     }
 }
 
-impl Grid {
+/// Ordinal directions are always empty
+impl Index<Direction8> for CardinalNeighbors {
+    type Output = Option<Neighbor>;
+
+    fn index(&self, direction: Direction8) -> &Self::Output {
+        match direction {
+            Direction8::North => &self.north,
+            Direction8::East => &self.east,
+            Direction8::South => &self.south,
+            Direction8::West => &self.west,
+            _ => NO_NEIGHBOR,
+        }
+    }
+}
+
+impl Index<Rotation> for CardinalNeighbors {
+    type Output = Option<Neighbor>;
+
+    fn index(&self, rotation: Rotation) -> &Self::Output {
+        &self[Direction8::from(rotation)]
+    }
+}
+
+impl<S: GridStorage> Grid<S> {
+    /// Get the cardinal neighbors of a cell
+    ///
+    /// Entities covering several cells are returned once, in the first direction they're found.
     pub fn get_cardinal_neighbors(&self, position: GridPosition) -> CardinalNeighbors {
+        let mut neighbors = self.cardinal_cells(position);
+        dedup_neighbors([
+            &mut neighbors.north,
+            &mut neighbors.east,
+            &mut neighbors.south,
+            &mut neighbors.west,
+        ]);
+        neighbors
+    }
+
+    /// Get the occupants of the cardinal cells next to a cell, one entry per cell
+    pub(crate) fn cardinal_cells(&self, position: GridPosition) -> CardinalNeighbors {
         CardinalNeighbors {
             north: self
                 .get(GridPosition::new(position.x, position.y + 1))
@@ -123,8 +159,23 @@ pub mod test {
                 .with_empty_entities(),
             CardinalNeighbors::seeded()
         );
+        let neighbors = grid.get_cardinal_neighbors(GridPosition::new(0, 0));
+        for direction in Direction8::CARDINAL {
+            assert_eq!(
+                neighbors[direction]
+                    .as_ref()
+                    .map(|neighbor| neighbor.position),
+                Some(GridPosition::new(0, 0).neighbor(direction))
+            );
+        }
+        assert!(
+            Direction8::ORDINAL
+                .iter()
+                .all(|direction| neighbors[*direction].is_none())
+        );
     }
 
+    #[allow(clippy::single_match)]
     pub mod seed {
         use super::*;
 
@@ -133,31 +184,19 @@ pub mod test {
                 let mut neighbors: CardinalNeighbors = CardinalNeighbors::new();
                 neighbors.north = Some(Neighbor {
                     position: GridPosition::new(0, 1),
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: Rotation::Up,
-                    },
+                    entry: GridEntity::new(Entity::PLACEHOLDER, Rotation::Up),
                 });
                 neighbors.east = Some(Neighbor {
                     position: GridPosition::new(1, 0),
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: Rotation::Right,
-                    },
+                    entry: GridEntity::new(Entity::PLACEHOLDER, Rotation::Right),
                 });
                 neighbors.south = Some(Neighbor {
                     position: GridPosition::new(0, -1),
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: Rotation::Down,
-                    },
+                    entry: GridEntity::new(Entity::PLACEHOLDER, Rotation::Down),
                 });
                 neighbors.west = Some(Neighbor {
                     position: GridPosition::new(-1, 0),
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: Rotation::Left,
-                    },
+                    entry: GridEntity::new(Entity::PLACEHOLDER, Rotation::Left),
                 });
                 neighbors
             }
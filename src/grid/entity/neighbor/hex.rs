@@ -0,0 +1,97 @@
+use std::ops::Index;
+
+use bevy::prelude::*;
+
+use super::dedup_neighbors;
+use crate::prelude::*;
+
+pub mod prelude {
+    pub use super::HexNeighbors;
+}
+
+/// The six neighbors of a cell on a hex grid
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Default)]
+pub struct HexNeighbors {
+    /// The neighbors, in `HexDirection::ALL` order
+    pub neighbors: [Option<Neighbor>; 6],
+}
+
+impl HexNeighbors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Iterate over the occupied neighbors with their direction
+    pub fn iter(&self) -> impl Iterator<Item = (HexDirection, &Neighbor)> {
+        HexDirection::ALL
+            .into_iter()
+            .zip(&self.neighbors)
+            .filter_map(|(direction, neighbor)| {
+                neighbor.as_ref().map(|neighbor| (direction, neighbor))
+            })
+    }
+}
+
+impl Index<HexDirection> for HexNeighbors {
+    type Output = Option<Neighbor>;
+
+    fn index(&self, direction: HexDirection) -> &Self::Output {
+        &self.neighbors[direction.index()]
+    }
+}
+
+impl<S: GridStorage> Grid<S> {
+    /// Get the six neighbors of a cell on a hex grid
+    ///
+    /// Entities covering several cells are returned once, in the first direction they're found.
+    pub fn get_hex_neighbors(&self, position: GridPosition) -> HexNeighbors {
+        let hex = HexPosition::from(position);
+        let mut neighbors = HexNeighbors {
+            neighbors: HexDirection::ALL.map(|direction| {
+                let neighbor_position = hex.neighbor(direction).into();
+                self.get(neighbor_position)
+                    .map(|entry| Neighbor::new(neighbor_position, entry))
+            }),
+        };
+        dedup_neighbors(&mut neighbors.neighbors);
+        neighbors
+    }
+
+    /// Get the occupied cells within `radius` steps of a cell on a hex grid, centre included
+    pub fn get_hex_radius_neighbors(&self, position: GridPosition, radius: i32) -> RadiusNeighbors {
+        self.get_radius_neighbors(position, RadiusQuery::hex(radius))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_neighbors() {
+        let mut grid = Grid::new();
+        let centre = HexPosition::new(2, -1);
+        for hex in centre.spiral(2) {
+            grid.insert(hex.into(), Entity::PLACEHOLDER, Rotation::Up);
+        }
+        // Empty the north west neighbor, the ring of 6 then has a single gap
+        grid.remove((centre + HexPosition::new(-1, 1)).into());
+
+        let neighbors = grid.get_hex_neighbors(centre.into());
+        assert_eq!(neighbors.iter().count(), 5);
+        assert_eq!(neighbors[HexDirection::NorthWest], None);
+        assert_eq!(
+            neighbors[HexDirection::SouthEast]
+                .as_ref()
+                .map(|neighbor| neighbor.position),
+            Some(GridPosition::new(3, -2))
+        );
+        assert_eq!(
+            grid.get_hex_radius_neighbors(centre.into(), 2)
+                .neighbors
+                .len(),
+            18
+        );
+    }
+}
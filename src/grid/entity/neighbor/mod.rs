@@ -1,24 +1,48 @@
+use bevy::{prelude::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
 use crate::prelude::*;
 
 pub mod cardinal;
+pub mod hex;
 pub mod ordinal;
 pub mod radius;
 
 pub mod prelude {
     pub use super::Neighbor;
     pub use super::cardinal::prelude::*;
+    pub use super::hex::prelude::*;
     pub use super::ordinal::prelude::*;
     pub use super::radius::prelude::*;
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
 pub struct Neighbor {
     pub position: GridPosition,
     pub entry: GridEntity,
 }
 
+/// The missing neighbor, returned when indexing neighbors in a direction they don't cover
+const NO_NEIGHBOR: &Option<Neighbor> = &None;
+
 impl Neighbor {
     pub fn new(position: GridPosition, entry: GridEntity) -> Self {
         Self { position, entry }
     }
 }
+
+/// Clear the neighbors whose entity was already found in an earlier one
+///
+/// Entities covering several cells, like those with a `GridFootprint`, are kept at the first cell
+/// only. `Entity::PLACEHOLDER` entries are never merged.
+fn dedup_neighbors<'a>(neighbors: impl IntoIterator<Item = &'a mut Option<Neighbor>>) {
+    let mut seen = HashSet::new();
+    for neighbor in neighbors {
+        let duplicate = neighbor.as_ref().is_some_and(|neighbor| {
+            neighbor.entry.entity != Entity::PLACEHOLDER && !seen.insert(neighbor.entry.entity)
+        });
+        if duplicate {
+            *neighbor = None;
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use std::ops::Index;
+
+use super::{NO_NEIGHBOR, dedup_neighbors};
 use crate::prelude::*;
 use bevy::prelude::*;
 
@@ -5,7 +8,8 @@ pub mod prelude {
     pub use super::OrdinalNeighbors;
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Default)]
 pub struct OrdinalNeighbors {
     pub north_west: Option<Neighbor>,
     pub north_east: Option<Neighbor>,
@@ -13,6 +17,12 @@ pub struct OrdinalNeighbors {
     pub south_west: Option<Neighbor>,
 }
 
+impl Default for OrdinalNeighbors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl OrdinalNeighbors {
     pub fn new() -> Self {
         Self {
@@ -25,81 +35,93 @@ impl OrdinalNeighbors {
 
     pub fn with_empty_entities(&self) -> Self {
         Self {
-            north_west: match self.north_west.clone() {
-                Some(entity) => Some(Neighbor {
-                    position: entity.position,
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: entity.entry.rotation,
-                    },
-                }),
-                None => None,
-            },
-            north_east: match self.north_east.clone() {
-                Some(entity) => Some(Neighbor {
-                    position: entity.position,
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: entity.entry.rotation,
-                    },
-                }),
-                None => None,
-            },
-            south_east: match self.south_east.clone() {
-                Some(entity) => Some(Neighbor {
-                    position: entity.position,
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: entity.entry.rotation,
-                    },
-                }),
-                None => None,
-            },
-            south_west: match self.south_west.clone() {
-                Some(entity) => Some(Neighbor {
-                    position: entity.position,
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: entity.entry.rotation,
-                    },
-                }),
-                None => None,
-            },
+            north_west: self.north_west.clone().map(|entity| Neighbor {
+                position: entity.position,
+                entry: GridEntity {
+                    entity: Entity::PLACEHOLDER,
+                    ..entity.entry
+                },
+            }),
+            north_east: self.north_east.clone().map(|entity| Neighbor {
+                position: entity.position,
+                entry: GridEntity {
+                    entity: Entity::PLACEHOLDER,
+                    ..entity.entry
+                },
+            }),
+            south_east: self.south_east.clone().map(|entity| Neighbor {
+                position: entity.position,
+                entry: GridEntity {
+                    entity: Entity::PLACEHOLDER,
+                    ..entity.entry
+                },
+            }),
+            south_west: self.south_west.clone().map(|entity| Neighbor {
+                position: entity.position,
+                entry: GridEntity {
+                    entity: Entity::PLACEHOLDER,
+                    ..entity.entry
+                },
+            }),
+        }
+    }
+}
+
+/// Cardinal directions are always empty
+impl Index<Direction8> for OrdinalNeighbors {
+    type Output = Option<Neighbor>;
+
+    fn index(&self, direction: Direction8) -> &Self::Output {
+        match direction {
+            Direction8::NorthWest => &self.north_west,
+            Direction8::NorthEast => &self.north_east,
+            Direction8::SouthEast => &self.south_east,
+            Direction8::SouthWest => &self.south_west,
+            _ => NO_NEIGHBOR,
         }
     }
 }
 
-impl Grid {
+impl<S: GridStorage> Grid<S> {
+    /// Get the ordinal neighbors of a cell
+    ///
+    /// Entities covering several cells are returned once, in the first direction they're found.
     pub fn get_ordinal_neighbors(&self, position: GridPosition) -> OrdinalNeighbors {
+        let mut neighbors = self.ordinal_cells(position);
+        dedup_neighbors([
+            &mut neighbors.north_west,
+            &mut neighbors.north_east,
+            &mut neighbors.south_east,
+            &mut neighbors.south_west,
+        ]);
+        neighbors
+    }
+
+    /// Get the occupants of the ordinal cells next to a cell, one entry per cell
+    pub(crate) fn ordinal_cells(&self, position: GridPosition) -> OrdinalNeighbors {
         OrdinalNeighbors {
-            north_west: match self.get(position + IVec2::new(-1, 1)) {
-                Some(entity) => Some(Neighbor {
-                    position: GridPosition::from(position + IVec2::new(-1, 1)),
-                    entry: entity,
-                }),
-                None => None,
-            },
-            north_east: match self.get(position + IVec2::new(1, 1)) {
-                Some(entity) => Some(Neighbor {
-                    position: GridPosition::from(position + IVec2::new(1, 1)),
-                    entry: entity,
+            north_west: self
+                .get(position + IVec2::new(-1, 1))
+                .map(|entry| Neighbor {
+                    position: position + IVec2::new(-1, 1),
+                    entry,
                 }),
-                None => None,
-            },
-            south_east: match self.get(position + IVec2::new(1, -1)) {
-                Some(entity) => Some(Neighbor {
-                    position: GridPosition::from(position + IVec2::new(1, -1)),
-                    entry: entity,
+            north_east: self.get(position + IVec2::new(1, 1)).map(|entry| Neighbor {
+                position: position + IVec2::new(1, 1),
+                entry,
+            }),
+            south_east: self
+                .get(position + IVec2::new(1, -1))
+                .map(|entry| Neighbor {
+                    position: position + IVec2::new(1, -1),
+                    entry,
                 }),
-                None => None,
-            },
-            south_west: match self.get(position + IVec2::new(-1, -1)) {
-                Some(entity) => Some(Neighbor {
-                    position: GridPosition::from(position + IVec2::new(-1, -1)),
-                    entry: entity,
+            south_west: self
+                .get(position + IVec2::new(-1, -1))
+                .map(|entry| Neighbor {
+                    position: position + IVec2::new(-1, -1),
+                    entry,
                 }),
-                None => None,
-            },
         }
     }
 }
@@ -127,8 +149,23 @@ mod tests {
                 .with_empty_entities(),
             OrdinalNeighbors::seeded(),
         );
+        let neighbors = grid.get_ordinal_neighbors(GridPosition::new(0, 0));
+        for direction in Direction8::ORDINAL {
+            assert_eq!(
+                neighbors[direction]
+                    .as_ref()
+                    .map(|neighbor| neighbor.position),
+                Some(GridPosition::new(0, 0).neighbor(direction))
+            );
+        }
+        assert!(
+            Direction8::CARDINAL
+                .iter()
+                .all(|direction| neighbors[*direction].is_none())
+        );
     }
 
+    #[allow(clippy::single_match)]
     pub mod seed {
         use super::*;
 
@@ -137,31 +174,19 @@ mod tests {
                 let mut neighbors: OrdinalNeighbors = OrdinalNeighbors::new();
                 neighbors.north_west = Some(Neighbor {
                     position: GridPosition::new(-1, 1),
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: Rotation::Up,
-                    },
+                    entry: GridEntity::new(Entity::PLACEHOLDER, Rotation::Up),
                 });
                 neighbors.north_east = Some(Neighbor {
                     position: GridPosition::new(1, 1),
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: Rotation::Right,
-                    },
+                    entry: GridEntity::new(Entity::PLACEHOLDER, Rotation::Right),
                 });
                 neighbors.south_east = Some(Neighbor {
                     position: GridPosition::new(1, -1),
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: Rotation::Down,
-                    },
+                    entry: GridEntity::new(Entity::PLACEHOLDER, Rotation::Down),
                 });
                 neighbors.south_west = Some(Neighbor {
                     position: GridPosition::new(-1, -1),
-                    entry: GridEntity {
-                        entity: Entity::PLACEHOLDER,
-                        rotation: Rotation::Left,
-                    },
+                    entry: GridEntity::new(Entity::PLACEHOLDER, Rotation::Left),
                 });
                 neighbors
             }
@@ -1,18 +1,26 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::prelude::*;
 
 pub mod prelude {
-    pub use super::RadiusNeighbors;
+    pub use super::{RadiusNeighbors, RadiusQuery, RadiusShape};
 }
 
 /// The neighbors of a grid entity
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Reflect)]
+#[reflect(Default)]
 pub struct RadiusNeighbors {
     /// The neighbors
     pub neighbors: Vec<Neighbor>,
 }
 
+impl Default for RadiusNeighbors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RadiusNeighbors {
     /// Create a new set of neighbors
     pub fn new() -> Self {
@@ -30,7 +38,7 @@ impl RadiusNeighbors {
                 .map(|neighbor| {
                     Neighbor::new(neighbor.position, GridEntity {
                         entity: Entity::PLACEHOLDER,
-                        rotation: neighbor.entry.rotation,
+                        ..neighbor.entry
                     })
                 })
                 .collect(),
@@ -38,57 +46,155 @@ impl RadiusNeighbors {
     }
 }
 
-impl Grid {
+/// The distance metric of a radius query
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum RadiusShape {
+    /// Euclidean distance rounded to the nearest cell, a circle
+    #[default]
+    Circle,
+    /// Manhattan distance, a diamond
+    Manhattan,
+    /// Chebyshev distance, a square
+    Chebyshev,
+    /// Hex distance of axial offsets, a hexagon on hex grids
+    Hex,
+}
+
+impl RadiusShape {
+    /// Get the distance of an offset from the centre
+    pub fn distance(&self, offset: IVec2) -> i32 {
+        match self {
+            Self::Circle => ((offset.length_squared() as f32).sqrt()).round() as i32,
+            Self::Manhattan => offset.x.abs() + offset.y.abs(),
+            Self::Chebyshev => offset.x.abs().max(offset.y.abs()),
+            Self::Hex => HexPosition::new(offset.x, offset.y).distance(HexPosition::ORIGIN),
+        }
+    }
+}
+
+/// The cells around a position within a range of distances
+///
+/// A ring of `min..=max` includes every cell at a distance of `min` to `max`,
+/// so consecutive rings of the same shape never overlap.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub struct RadiusQuery {
+    /// The distance metric
+    pub shape: RadiusShape,
+    /// The minimum distance, included
+    pub min: i32,
+    /// The maximum distance, included
+    pub max: i32,
+    /// Whether to leave out the centre cell
+    pub exclude_centre: bool,
+}
+
+impl RadiusQuery {
+    /// Create a query of a shape with the given radius
+    pub fn new(shape: RadiusShape, radius: i32) -> Self {
+        Self {
+            shape,
+            min: 0,
+            max: radius,
+            exclude_centre: false,
+        }
+    }
+
+    /// Create a rounded circle query
+    pub fn circle(radius: i32) -> Self {
+        Self::new(RadiusShape::Circle, radius)
+    }
+
+    /// Create a Manhattan diamond query
+    pub fn manhattan(radius: i32) -> Self {
+        Self::new(RadiusShape::Manhattan, radius)
+    }
+
+    /// Create a Chebyshev square query
+    pub fn chebyshev(radius: i32) -> Self {
+        Self::new(RadiusShape::Chebyshev, radius)
+    }
+
+    /// Create a hexagon query, for hex grids
+    pub fn hex(radius: i32) -> Self {
+        Self::new(RadiusShape::Hex, radius)
+    }
+
+    /// Create a ring query, including cells at a distance of `min` to `max`
+    pub fn ring(shape: RadiusShape, min: i32, max: i32) -> Self {
+        Self {
+            min,
+            ..Self::new(shape, max)
+        }
+    }
+
+    /// Leave out the centre cell
+    pub fn excluding_centre(mut self) -> Self {
+        self.exclude_centre = true;
+        self
+    }
+
+    /// Whether an offset from the centre is part of the query
+    pub fn contains(&self, offset: IVec2) -> bool {
+        if self.exclude_centre && offset == IVec2::ZERO {
+            return false;
+        }
+        (self.min..=self.max).contains(&self.shape.distance(offset))
+    }
+
+    /// Iterate over the offsets of the query, column by column
+    pub fn offsets(self) -> impl Iterator<Item = IVec2> {
+        let radius = self.max.max(0);
+        (-radius..=radius)
+            .flat_map(move |x| (-radius..=radius).map(move |y| IVec2::new(x, y)))
+            .filter(move |offset| self.contains(*offset))
+    }
+}
+
+impl<S: GridStorage> Grid<S> {
+    /// Iterate over the occupied cells of a radius query around a position
+    pub fn iter_radius_neighbors(
+        &self,
+        position: GridPosition,
+        query: RadiusQuery,
+    ) -> impl Iterator<Item = Neighbor> + '_ {
+        // Offsets run along columns, so nearby lookups follow each other
+        let mut lookup = self.storage().lookup();
+        query.offsets().filter_map(move |offset| {
+            let neighbor_position = position + offset;
+            lookup(neighbor_position).map(|entry| Neighbor::new(neighbor_position, entry))
+        })
+    }
+
+    /// Get the occupied cells of a radius query around a position
+    pub fn get_radius_neighbors(
+        &self,
+        position: GridPosition,
+        query: RadiusQuery,
+    ) -> RadiusNeighbors {
+        RadiusNeighbors {
+            neighbors: self.iter_radius_neighbors(position, query).collect(),
+        }
+    }
+
     /// Get the neighbors of a grid entity
     /// This will return the neighbors of the entity at the given position
-    /// This will return a square of neighbors with the given radius
+    /// This will return a square of neighbors with the given radius, centre included
     pub fn get_square_radius_neighbors(
         &self,
         position: GridPosition,
         radius: i32,
     ) -> RadiusNeighbors {
-        let mut returned_neighbors: RadiusNeighbors = RadiusNeighbors::new();
-        for x in -radius..=radius {
-            for y in -radius..=radius {
-                let neighbor_position = GridPosition {
-                    x: position.x + x,
-                    y: position.y + y,
-                };
-
-                if let Some(neighbor) = self.get(neighbor_position) {
-                    returned_neighbors
-                        .neighbors
-                        .push(Neighbor::new(neighbor_position, neighbor));
-                }
-            }
-        }
-        returned_neighbors
+        self.get_radius_neighbors(position, RadiusQuery::chebyshev(radius))
     }
 
-    // Get the neighbors of a position with a radius
-    // This return should be rounded to only include neighbors that are X/radius from the position
+    /// Get the neighbors of a position with a radius
+    /// This will return a circle of neighbors, rounded to the nearest cell, centre included
     pub fn get_rounded_radius_neighbors(
         &self,
         position: GridPosition,
         radius: i32,
     ) -> RadiusNeighbors {
-        let mut returned_neighbors: RadiusNeighbors = RadiusNeighbors::new();
-        for x in -radius..=radius {
-            for y in -radius..=radius {
-                let neighbor_position = GridPosition {
-                    x: position.x + x,
-                    y: position.y + y,
-                };
-
-                if let Some(neighbor) = self.get(neighbor_position) {
-                    returned_neighbors
-                        .neighbors
-                        .push(Neighbor::new(neighbor_position, neighbor));
-                }
-            }
-        }
-
-        returned_neighbors
+        self.get_radius_neighbors(position, RadiusQuery::circle(radius))
     }
 }
 
@@ -115,6 +221,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_radius_shapes() {
+        let count = |query: RadiusQuery| query.offsets().count();
+        assert_eq!(count(RadiusQuery::chebyshev(2)), 25);
+        assert_eq!(count(RadiusQuery::manhattan(2)), 13);
+        // The rounded circle of radius 2 is the square without its corners
+        assert_eq!(count(RadiusQuery::circle(2)), 21);
+        assert!(!RadiusQuery::circle(2).contains(IVec2::new(2, 2)));
+        assert!(RadiusQuery::circle(2).contains(IVec2::new(2, 1)));
+
+        assert_eq!(count(RadiusQuery::chebyshev(1).excluding_centre()), 8);
+        assert_eq!(count(RadiusQuery::hex(2)), 19);
+        assert_eq!(count(RadiusQuery::ring(RadiusShape::Hex, 2, 2)), 12);
+        assert_eq!(count(RadiusQuery::ring(RadiusShape::Chebyshev, 2, 2)), 16);
+        // Consecutive rings cover the whole circle exactly once
+        let rings = (0..=3)
+            .map(|radius| count(RadiusQuery::ring(RadiusShape::Circle, radius, radius)))
+            .sum::<usize>();
+        assert_eq!(rings, count(RadiusQuery::circle(3)));
+    }
+
+    #[test]
+    fn test_rounded_radius_neighbors() {
+        let mut grid = Grid::new();
+        for x in -2..=2 {
+            for y in -2..=2 {
+                grid.insert(GridPosition::new(x, y), Entity::PLACEHOLDER, Rotation::Up);
+            }
+        }
+        let centre = GridPosition::new(0, 0);
+        assert_eq!(
+            grid.get_square_radius_neighbors(centre, 2).neighbors.len(),
+            25
+        );
+        assert_eq!(
+            grid.get_rounded_radius_neighbors(centre, 2).neighbors.len(),
+            21
+        );
+        assert_eq!(
+            grid.iter_radius_neighbors(centre, RadiusQuery::manhattan(1).excluding_centre())
+                .map(|neighbor| neighbor.position)
+                .collect::<Vec<_>>(),
+            vec![
+                GridPosition::new(-1, 0),
+                GridPosition::new(0, -1),
+                GridPosition::new(0, 1),
+                GridPosition::new(1, 0),
+            ]
+        );
+    }
+
     pub mod seed {
         use super::*;
 
@@ -127,12 +284,10 @@ mod tests {
                         if x == 0 && y == 0 {
                             continue;
                         }
-                        neighbors
-                            .neighbors
-                            .push(Neighbor::new(position, GridEntity {
-                                entity: Entity::PLACEHOLDER,
-                                rotation: Rotation::default(),
-                            }));
+                        neighbors.neighbors.push(Neighbor::new(
+                            position,
+                            GridEntity::new(Entity::PLACEHOLDER, Rotation::default()),
+                        ));
                     }
                 }
                 neighbors
@@ -1,23 +1,23 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
 pub mod prelude {
-    pub use super::Rotation;
+    pub use super::{GridRotation, Rotation};
 }
 
 pub const EMPTY: Rotation = Rotation::Up;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
 pub enum Rotation {
+    #[default]
     Up,
     Down,
     Left,
     Right,
 }
 
-impl Default for Rotation {
-    fn default() -> Self {
-        Self::Up
-    }
-}
-
 impl Rotation {
     pub fn next(&self) -> Self {
         match self {
@@ -49,9 +49,81 @@ impl Rotation {
     pub fn to_angle(&self) -> f32 {
         match self {
             Self::Up => 0.0,
-            Self::Right => 1.5707964,
-            Self::Down => 3.1415927,
-            Self::Left => 4.712389,
+            Self::Right => FRAC_PI_2,
+            Self::Down => PI,
+            Self::Left => 3.0 * FRAC_PI_2,
+        }
+    }
+
+    /// Get the number of clockwise quarter turns from `Up`
+    pub fn quarter_turns(&self) -> i32 {
+        match self {
+            Self::Up => 0,
+            Self::Right => 1,
+            Self::Down => 2,
+            Self::Left => 3,
+        }
+    }
+
+    /// Get the rotation after a number of clockwise quarter turns from `Up`, negative turns go counterclockwise
+    pub fn from_quarter_turns(turns: i32) -> Self {
+        match turns.rem_euclid(4) {
+            0 => Self::Up,
+            1 => Self::Right,
+            2 => Self::Down,
+            _ => Self::Left,
+        }
+    }
+
+    /// Turn by another rotation, as if `Up` was `self`
+    pub fn compose(&self, other: Self) -> Self {
+        Self::from_quarter_turns(self.quarter_turns() + other.quarter_turns())
+    }
+
+    /// Get the rotation undoing this one
+    pub fn inverse(&self) -> Self {
+        Self::from_quarter_turns(-self.quarter_turns())
+    }
+
+    /// Get the unit offset the rotation faces, `Up` being grid y
+    pub fn to_ivec2(&self) -> IVec2 {
+        match self {
+            Self::Up => IVec2::Y,
+            Self::Right => IVec2::X,
+            Self::Down => IVec2::NEG_Y,
+            Self::Left => IVec2::NEG_X,
+        }
+    }
+
+    /// Get the rotation closest to a direction vector
+    ///
+    /// Returns `None` for the zero vector and for exact diagonals, which are as close to two rotations.
+    pub fn from_ivec2(direction: IVec2) -> Option<Self> {
+        let (x, y) = (direction.x.abs(), direction.y.abs());
+        if x == y {
+            None
+        } else if y > x {
+            Some(if direction.y > 0 {
+                Self::Up
+            } else {
+                Self::Down
+            })
+        } else {
+            Some(if direction.x > 0 {
+                Self::Right
+            } else {
+                Self::Left
+            })
+        }
+    }
+
+    /// Turn an offset like the rotation turns grid y towards grid x
+    pub fn rotate_offset(&self, offset: IVec2) -> IVec2 {
+        match self {
+            Self::Up => offset,
+            Self::Right => IVec2::new(offset.y, -offset.x),
+            Self::Down => -offset,
+            Self::Left => IVec2::new(-offset.y, offset.x),
         }
     }
 
@@ -66,6 +138,36 @@ impl Rotation {
     }
 }
 
+impl std::ops::Add for Rotation {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.compose(rhs)
+    }
+}
+
+impl std::ops::Sub for Rotation {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.compose(rhs.inverse())
+    }
+}
+
+/// The rotation of an entity in the grid
+/// Entities without this component use the spawn rotation of the grid state
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Component, Reflect, Serialize, Deserialize,
+)]
+#[reflect(Component, Default)]
+pub struct GridRotation(pub Rotation);
+
+impl From<Rotation> for GridRotation {
+    fn from(rotation: Rotation) -> Self {
+        Self(rotation)
+    }
+}
+
 #[cfg(test)]
 pub mod test {
     use super::*;
@@ -95,10 +197,34 @@ pub mod test {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_rotation_to_angle() {
         assert_eq!(Rotation::Up.to_angle(), 0.0);
         assert_eq!(Rotation::Right.to_angle(), 1.5707964);
         assert_eq!(Rotation::Down.to_angle(), 3.1415927);
         assert_eq!(Rotation::Left.to_angle(), 4.712389);
     }
+
+    #[test]
+    fn test_rotation_arithmetic() {
+        assert_eq!(Rotation::Right + Rotation::Down, Rotation::Left);
+        assert_eq!(Rotation::Up - Rotation::Right, Rotation::Left);
+        assert_eq!(Rotation::from_quarter_turns(-1), Rotation::Left);
+        assert_eq!(Rotation::Right.inverse(), Rotation::Left);
+        assert_eq!(Rotation::Right.to_ivec2(), IVec2::X);
+        assert_eq!(
+            Rotation::from_ivec2(IVec2::new(-3, 1)),
+            Some(Rotation::Left)
+        );
+        assert_eq!(Rotation::from_ivec2(IVec2::new(2, 2)), None);
+        // Turning the offset facing up gives the offset facing the rotation
+        for rotation in [
+            Rotation::Up,
+            Rotation::Right,
+            Rotation::Down,
+            Rotation::Left,
+        ] {
+            assert_eq!(rotation.rotate_offset(IVec2::Y), rotation.to_ivec2());
+        }
+    }
 }
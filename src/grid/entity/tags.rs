@@ -0,0 +1,225 @@
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub mod prelude {
+    pub use super::{GridTags, TagFilter};
+}
+
+use crate::prelude::*;
+
+/// A set of up to 64 user-defined kinds or tags of an entity, stored in its grid cells
+///
+/// The meaning of each bit is up to the game, for instance `WALL = GridTags::bit(0)`.
+/// Entities without this component have no tags.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Component, Reflect, Serialize, Deserialize,
+)]
+#[reflect(Component, Default)]
+pub struct GridTags(pub u64);
+
+impl GridTags {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(u64::MAX);
+
+    /// Get the tags with only the given bit set, from 0 to 63
+    pub const fn bit(index: u32) -> Self {
+        Self(1 << index)
+    }
+
+    /// Whether every tag of `other` is set
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether any tag of `other` is set
+    pub fn intersects(&self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+
+    /// Get these tags with the tags of `other` set
+    pub fn with(self, other: Self) -> Self {
+        self | other
+    }
+}
+
+impl BitOr for GridTags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for GridTags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for GridTags {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+/// Which tags a grid entry must have to be matched
+///
+/// The default filter matches every entry.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub struct TagFilter {
+    /// The tags that must all be set
+    pub all: GridTags,
+    /// The tags of which at least one must be set, ignored if empty
+    pub any: GridTags,
+    /// The tags that must not be set
+    pub none: GridTags,
+}
+
+impl TagFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require every one of the tags
+    pub fn with_all(mut self, tags: GridTags) -> Self {
+        self.all |= tags;
+        self
+    }
+
+    /// Require at least one of the tags
+    pub fn with_any(mut self, tags: GridTags) -> Self {
+        self.any |= tags;
+        self
+    }
+
+    /// Reject the entries with any of the tags
+    pub fn without(mut self, tags: GridTags) -> Self {
+        self.none |= tags;
+        self
+    }
+
+    pub fn matches(&self, tags: GridTags) -> bool {
+        tags.contains(self.all)
+            && (self.any.is_empty() || tags.intersects(self.any))
+            && !tags.intersects(self.none)
+    }
+
+    pub fn matches_entry(&self, entry: &GridEntity) -> bool {
+        self.matches(entry.tags)
+    }
+}
+
+impl From<GridTags> for TagFilter {
+    fn from(tags: GridTags) -> Self {
+        Self::new().with_all(tags)
+    }
+}
+
+impl<S: GridStorage> Grid<S> {
+    /// Get the occupied neighbors of a cell whose tags match a filter, cardinal first
+    pub fn get_tagged_neighbors(
+        &self,
+        position: GridPosition,
+        connectivity: Connectivity,
+        filter: impl Into<TagFilter>,
+    ) -> Vec<Neighbor> {
+        let filter = filter.into();
+        self.get_connected_neighbors(position, connectivity)
+            .into_iter()
+            .filter(|neighbor| filter.matches_entry(&neighbor.entry))
+            .collect()
+    }
+
+    /// Get the occupied cells of a radius query around a position whose tags match a filter
+    pub fn get_tagged_radius_neighbors(
+        &self,
+        position: GridPosition,
+        query: RadiusQuery,
+        filter: impl Into<TagFilter>,
+    ) -> RadiusNeighbors {
+        let filter = filter.into();
+        RadiusNeighbors {
+            neighbors: self
+                .iter_radius_neighbors(position, query)
+                .filter(|neighbor| filter.matches_entry(&neighbor.entry))
+                .collect(),
+        }
+    }
+
+    /// Get the cells connected to a start cell whose tags match a filter, start included
+    pub fn flood_fill_tagged(
+        &self,
+        start: GridPosition,
+        connectivity: Connectivity,
+        filter: impl Into<TagFilter>,
+    ) -> Vec<Neighbor> {
+        let filter = filter.into();
+        self.flood_fill(start, connectivity, |entry| filter.matches_entry(entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALL: GridTags = GridTags::bit(0);
+    const DOOR: GridTags = GridTags::bit(1);
+    const WOOD: GridTags = GridTags::bit(2);
+
+    #[test]
+    fn test_tag_filter() {
+        let wooden_door = DOOR | WOOD;
+        assert!(wooden_door.contains(DOOR));
+        assert!(!wooden_door.contains(WALL | DOOR));
+        assert!(wooden_door.intersects(WALL | DOOR));
+
+        assert!(TagFilter::new().matches(GridTags::NONE));
+        assert!(TagFilter::from(DOOR).matches(wooden_door));
+        assert!(!TagFilter::from(DOOR).with_all(WALL).matches(wooden_door));
+        assert!(TagFilter::new().with_any(WALL | DOOR).matches(wooden_door));
+        assert!(!TagFilter::new().with_any(WALL).matches(wooden_door));
+        assert!(!TagFilter::new().without(WOOD).matches(wooden_door));
+    }
+
+    #[test]
+    fn test_tagged_queries() {
+        let mut grid = Grid::new();
+        let entity = Entity::PLACEHOLDER;
+        for x in 0..4 {
+            grid.insert(GridPosition::new(x, 0), entity, Rotation::Up);
+            grid.get_mut(GridPosition::new(x, 0)).unwrap().tags = WALL;
+        }
+        grid.get_mut(GridPosition::new(2, 0)).unwrap().tags = DOOR;
+        grid.insert(GridPosition::new(1, 1), entity, Rotation::Up);
+
+        let walls = grid.get_tagged_neighbors(GridPosition::new(1, 0), Connectivity::Four, WALL);
+        assert_eq!(walls.len(), 1);
+        assert_eq!(walls[0].position, GridPosition::new(0, 0));
+        let around = grid.get_tagged_radius_neighbors(
+            GridPosition::new(1, 0),
+            RadiusQuery::chebyshev(2),
+            TagFilter::new().without(DOOR),
+        );
+        assert_eq!(around.neighbors.len(), 4);
+
+        // The door splits the walls
+        let filled = grid.flood_fill_tagged(GridPosition::new(0, 0), Connectivity::Eight, WALL);
+        assert_eq!(filled.len(), 2);
+    }
+}
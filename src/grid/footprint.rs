@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub mod prelude {
+    pub use super::GridFootprint;
+}
+
+use crate::prelude::*;
+
+/// The cells covered by an entity larger than one cell, relative to its `GridPosition`
+///
+/// The cells are given for `Rotation::Up` and turn with the rotation of the entity, around the
+/// anchor cell. The entity is registered in every covered cell and its model is centred over
+/// the footprint. Entities without this component cover their anchor cell only.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Default)]
+pub struct GridFootprint {
+    /// The covered offsets from the anchor
+    pub cells: Vec<IVec2>,
+}
+
+impl Default for GridFootprint {
+    fn default() -> Self {
+        Self::single()
+    }
+}
+
+impl GridFootprint {
+    /// A footprint covering the anchor cell only
+    pub fn single() -> Self {
+        Self {
+            cells: vec![IVec2::ZERO],
+        }
+    }
+
+    /// A `width` by `height` rectangle with the anchor in its corner with the lowest coordinates
+    pub fn rectangle(width: u32, height: u32) -> Self {
+        let (width, height) = (width.max(1) as i32, height.max(1) as i32);
+        Self::from_cells((0..height).flat_map(|y| (0..width).map(move |x| IVec2::new(x, y))))
+    }
+
+    /// A footprint covering arbitrary offsets from the anchor
+    ///
+    /// The anchor is always covered, duplicates are removed.
+    pub fn from_cells(cells: impl IntoIterator<Item = IVec2>) -> Self {
+        let mut covered = vec![IVec2::ZERO];
+        for cell in cells {
+            if !covered.contains(&cell) {
+                covered.push(cell);
+            }
+        }
+        Self { cells: covered }
+    }
+
+    /// Whether the footprint covers more than its anchor
+    pub fn is_multi_cell(&self) -> bool {
+        self.cells.iter().any(|cell| *cell != IVec2::ZERO)
+    }
+
+    /// Iterate over the offsets turned by a rotation
+    pub fn offsets(&self, rotation: Rotation) -> impl Iterator<Item = IVec2> + '_ {
+        self.cells
+            .iter()
+            .map(move |cell| rotation.rotate_offset(*cell))
+    }
+
+    /// Iterate over the covered cells of an anchor, turned by a rotation
+    pub fn cells_at(
+        &self,
+        anchor: GridPosition,
+        rotation: Rotation,
+    ) -> impl Iterator<Item = GridPosition> + '_ {
+        self.offsets(rotation).map(move |offset| anchor + offset)
+    }
+}
+
+impl<S: GridStorage> Grid<S> {
+    /// Get the entries blocking a footprint, ignoring the cells of `entity`
+    ///
+    /// Returns an error if a cell of the footprint is out of bounds.
+    pub fn footprint_collisions(
+        &self,
+        anchor: GridPosition,
+        footprint: &GridFootprint,
+        rotation: Rotation,
+        entity: Entity,
+    ) -> Result<Vec<GridEntity>, GridOutOfBounds> {
+        let mut collisions = Vec::new();
+        for cell in footprint.cells_at(anchor, rotation) {
+            if !self.in_bounds(cell) {
+                return Err(GridOutOfBounds(cell));
+            }
+            if let Some(entry) = self.get(cell)
+                && entry.entity != entity
+                && !collisions.contains(&entry)
+            {
+                collisions.push(entry);
+            }
+        }
+        Ok(collisions)
+    }
+
+    /// Whether a footprint fits without covering another entity
+    pub fn footprint_fits(
+        &self,
+        anchor: GridPosition,
+        footprint: &GridFootprint,
+        rotation: Rotation,
+        entity: Entity,
+    ) -> bool {
+        self.footprint_collisions(anchor, footprint, rotation, entity)
+            .is_ok_and(|collisions| collisions.is_empty())
+    }
+
+    /// Register an entity in every cell of a footprint, replacing the previous entries
+    pub fn insert_footprint(
+        &mut self,
+        anchor: GridPosition,
+        footprint: &GridFootprint,
+        entity: Entity,
+        rotation: Rotation,
+    ) {
+        for cell in footprint.cells_at(anchor, rotation) {
+            self.insert(cell, entity, rotation);
+        }
+    }
+
+    /// Remove an entity from every cell of a footprint, leaving cells of other entities alone
+    pub fn remove_footprint(
+        &mut self,
+        anchor: GridPosition,
+        footprint: &GridFootprint,
+        rotation: Rotation,
+        entity: Entity,
+    ) {
+        for cell in footprint.cells_at(anchor, rotation) {
+            if self.get(cell).is_some_and(|entry| entry.entity == entity) {
+                self.remove(cell);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_footprint_rotation() {
+        let footprint = GridFootprint::rectangle(2, 3);
+        assert_eq!(footprint.cells.len(), 6);
+        let anchor = GridPosition::new(5, 5);
+        let right = footprint
+            .cells_at(anchor, Rotation::Right)
+            .collect::<Vec<_>>();
+        // Turned right, the 2x3 rectangle lies 3 wide and 2 deep
+        assert!(right.contains(&GridPosition::new(7, 5)));
+        assert!(right.contains(&GridPosition::new(5, 4)));
+        assert!(
+            right
+                .iter()
+                .all(|cell| (5..=7).contains(&cell.x) && (4..=5).contains(&cell.y))
+        );
+        assert_eq!(GridFootprint::from_cells([IVec2::X, IVec2::X]).cells, vec![
+            IVec2::ZERO,
+            IVec2::X
+        ]);
+    }
+
+    #[test]
+    fn test_footprint_collisions() {
+        let mut grid = Grid::new();
+        let building = Entity::from_raw(1);
+        let rock = Entity::from_raw(2);
+        let footprint = GridFootprint::rectangle(2, 2);
+        grid.insert_footprint(GridPosition::new(0, 0), &footprint, building, Rotation::Up);
+        grid.insert(GridPosition::new(3, 0), rock, Rotation::Up);
+        assert_eq!(grid.len(), 5);
+
+        // The building doesn't block itself
+        assert!(grid.footprint_fits(GridPosition::new(1, 0), &footprint, Rotation::Up, building));
+        assert_eq!(
+            grid.footprint_collisions(GridPosition::new(1, 0), &footprint, Rotation::Up, rock),
+            Ok(vec![GridEntity::new(building, Rotation::Up)])
+        );
+
+        grid.remove_footprint(GridPosition::new(0, 0), &footprint, Rotation::Up, building);
+        assert_eq!(grid.len(), 1);
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+
+pub mod prelude {
+    pub use super::shadowcast;
+}
+
+use crate::prelude::*;
+
+/// A slope as a fraction, the denominator being positive
+#[derive(Debug, Copy, Clone)]
+struct Slope {
+    numerator: i32,
+    denominator: i32,
+}
+
+impl Slope {
+    /// The slope from the origin to the edge of a cell shared with the previous column
+    fn of(depth: i32, column: i32) -> Self {
+        Self {
+            numerator: 2 * column - 1,
+            denominator: 2 * depth,
+        }
+    }
+}
+
+/// A row of a quadrant, between two slopes
+#[derive(Debug, Copy, Clone)]
+struct Row {
+    depth: i32,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row {
+    /// Get the first column of the row, rounding ties up
+    fn min_column(&self) -> i32 {
+        let Slope {
+            numerator,
+            denominator,
+        } = self.start;
+        (2 * self.depth * numerator + denominator).div_euclid(2 * denominator)
+    }
+
+    /// Get the last column of the row, rounding ties down
+    fn max_column(&self) -> i32 {
+        let Slope {
+            numerator,
+            denominator,
+        } = self.end;
+        -(denominator - 2 * self.depth * numerator).div_euclid(2 * denominator)
+    }
+
+    /// Whether the centre of a cell is between the slopes, which keeps the field of view symmetric
+    fn is_symmetric(&self, column: i32) -> bool {
+        column * self.start.denominator >= self.depth * self.start.numerator
+            && column * self.end.denominator <= self.depth * self.end.numerator
+    }
+
+    fn next(&self) -> Self {
+        Self {
+            depth: self.depth + 1,
+            ..*self
+        }
+    }
+}
+
+/// Get the cell at a depth and column of one of the four quadrants around an origin
+fn quadrant_cell(origin: GridPosition, quadrant: usize, depth: i32, column: i32) -> GridPosition {
+    let offset = match quadrant {
+        0 => IVec2::new(column, depth),
+        1 => IVec2::new(depth, -column),
+        2 => IVec2::new(-column, -depth),
+        _ => IVec2::new(-depth, column),
+    };
+    origin + offset
+}
+
+/// Get the cells visible from an origin within a range, with symmetric shadowcasting
+///
+/// Opaque cells are visible but hide the cells behind them. The field of view is symmetric: a
+/// cell sees the origin whenever the origin sees it. Cells further than `range` are not visible.
+pub fn shadowcast(
+    origin: GridPosition,
+    range: u32,
+    is_opaque: impl Fn(GridPosition) -> bool,
+) -> CellBitmap {
+    let range = range as i32;
+    let mut visible = CellBitmap::new();
+    visible.insert(origin);
+
+    for quadrant in 0..4 {
+        let mut rows = vec![Row {
+            depth: 1,
+            start: Slope {
+                numerator: -1,
+                denominator: 1,
+            },
+            end: Slope {
+                numerator: 1,
+                denominator: 1,
+            },
+        }];
+        while let Some(mut row) = rows.pop() {
+            if row.depth > range {
+                continue;
+            }
+            let mut previous_opaque = None;
+            for column in row.min_column()..=row.max_column() {
+                let cell = quadrant_cell(origin, quadrant, row.depth, column);
+                let opaque = is_opaque(cell);
+                let in_range = row.depth * row.depth + column * column <= range * range;
+                if in_range && (opaque || row.is_symmetric(column)) {
+                    visible.insert(cell);
+                }
+                match previous_opaque {
+                    Some(true) if !opaque => row.start = Slope::of(row.depth, column),
+                    Some(false) if opaque => rows.push(Row {
+                        end: Slope::of(row.depth, column),
+                        ..row.next()
+                    }),
+                    _ => {}
+                }
+                previous_opaque = Some(opaque);
+            }
+            if previous_opaque == Some(false) {
+                rows.push(row.next());
+            }
+        }
+    }
+    visible
+}
+
+impl<S: GridStorage> Grid<S> {
+    /// Get the cells visible from a position within a range
+    ///
+    /// `blocks` decides which occupants block sight, see `shadowcast`.
+    pub fn field_of_view(
+        &self,
+        origin: GridPosition,
+        range: u32,
+        blocks: impl Fn(&Neighbor) -> bool,
+    ) -> CellBitmap {
+        shadowcast(origin, range, |position| {
+            self.get(position)
+                .is_some_and(|entry| blocks(&Neighbor::new(position, entry)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_of_view() {
+        let mut grid = Grid::new();
+        let wall = Entity::PLACEHOLDER;
+        for y in -1..=1 {
+            grid.insert(GridPosition::new(2, y), wall, Rotation::Up);
+        }
+        let origin = GridPosition::new(0, 0);
+        let visible = grid.field_of_view(origin, 5, |_| true);
+
+        assert!(visible.contains(origin));
+        // The wall is seen, the cells behind it are not
+        assert!(visible.contains(GridPosition::new(2, 0)));
+        assert!(!visible.contains(GridPosition::new(3, 0)));
+        assert!(!visible.contains(GridPosition::new(5, 1)));
+        assert!(visible.contains(GridPosition::new(-5, 0)));
+        assert!(visible.contains(GridPosition::new(0, 5)));
+        // Out of range
+        assert!(!visible.contains(GridPosition::new(4, 4)));
+        assert!(!visible.contains(GridPosition::new(-6, 0)));
+
+        // Sight is symmetric
+        for cell in visible.iter() {
+            if grid.contains(cell) {
+                continue;
+            }
+            assert!(grid.field_of_view(cell, 5, |_| true).contains(origin));
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use std::f32::consts::{FRAC_PI_3, FRAC_PI_6};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub mod prelude {
+    pub use super::{HexDirection, HexFacing, HexOrientation, HexPosition};
+}
+
+use crate::prelude::*;
+
+const SQRT_3: f32 = 1.732_050_8;
+
+/// How the hexagons of a hex grid are laid out
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum HexOrientation {
+    /// A corner points along grid y, rows of hexagons run along grid x
+    #[default]
+    Pointy,
+    /// A flat side faces grid y, columns of hexagons run along grid y
+    Flat,
+}
+
+impl HexOrientation {
+    /// Get the grid space centre of a hexagon with a size (centre to corner) of 1
+    pub fn to_plane(&self, hex: HexPosition) -> Vec2 {
+        let (q, r) = (hex.q as f32, hex.r as f32);
+        match self {
+            Self::Pointy => Vec2::new(SQRT_3 * q + SQRT_3 / 2.0 * r, 1.5 * r),
+            Self::Flat => Vec2::new(1.5 * q, SQRT_3 / 2.0 * q + SQRT_3 * r),
+        }
+    }
+
+    /// Get the hexagon containing a grid space point, for hexagons with a size of 1
+    pub fn from_plane(&self, point: Vec2) -> HexPosition {
+        let (q, r) = match self {
+            Self::Pointy => (SQRT_3 / 3.0 * point.x - point.y / 3.0, 2.0 / 3.0 * point.y),
+            Self::Flat => (2.0 / 3.0 * point.x, -point.x / 3.0 + SQRT_3 / 3.0 * point.y),
+        };
+        HexPosition::round(q, r)
+    }
+
+    /// Get the angle from grid x to the first corner of a hexagon
+    pub fn corner_angle(&self) -> f32 {
+        match self {
+            Self::Pointy => FRAC_PI_6,
+            Self::Flat => 0.0,
+        }
+    }
+
+    /// Get the angle from grid x to a direction
+    pub fn direction_angle(&self, direction: HexDirection) -> f32 {
+        let start = match self {
+            Self::Pointy => 0.0,
+            Self::Flat => FRAC_PI_6,
+        };
+        start + direction.index() as f32 * FRAC_PI_3
+    }
+}
+
+/// A cell of a hex grid in axial coordinates
+///
+/// Hex cells are stored in a `Grid` as the `GridPosition` `(q, r)`, see `EntityGridSettings::lattice`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub struct HexPosition {
+    pub q: i32,
+    pub r: i32,
+}
+
+impl HexPosition {
+    pub const ORIGIN: Self = Self { q: 0, r: 0 };
+
+    pub fn new(q: i32, r: i32) -> Self {
+        Self { q, r }
+    }
+
+    /// Create a hex position from cube coordinates, which must sum to zero
+    pub fn from_cube(q: i32, r: i32, s: i32) -> Self {
+        debug_assert_eq!(q + r + s, 0);
+        Self { q, r }
+    }
+
+    /// Get the third cube coordinate
+    pub fn s(&self) -> i32 {
+        -self.q - self.r
+    }
+
+    /// Get the cube coordinates `(q, r, s)`
+    pub fn cube(&self) -> IVec3 {
+        IVec3::new(self.q, self.r, self.s())
+    }
+
+    /// Round fractional axial coordinates to the nearest hex
+    pub fn round(q: f32, r: f32) -> Self {
+        let s = -q - r;
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        // Fix the coordinate that was rounded the most so the three sum to zero
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+        Self::new(rq as i32, rr as i32)
+    }
+
+    /// Get the neighbor in a direction
+    pub fn neighbor(&self, direction: HexDirection) -> Self {
+        *self + direction.offset()
+    }
+
+    /// Iterate over the six neighbors, in `HexDirection::ALL` order
+    pub fn neighbors(&self) -> impl Iterator<Item = Self> + use<> {
+        let centre = *self;
+        HexDirection::ALL
+            .into_iter()
+            .map(move |direction| centre.neighbor(direction))
+    }
+
+    /// Get the number of steps between two hexes
+    pub fn distance(&self, other: Self) -> i32 {
+        let delta = (*self - other).cube().abs();
+        delta.x.max(delta.y).max(delta.z)
+    }
+
+    /// Rotate around another hex by sixths of a turn, clockwise for positive steps
+    pub fn rotate_around(&self, centre: Self, steps: i32) -> Self {
+        let mut offset = *self - centre;
+        for _ in 0..steps.rem_euclid(6) {
+            // Rotating clockwise by one sixth maps (q, r, s) to (-s, -q, -r)
+            offset = Self::new(-offset.s(), -offset.q);
+        }
+        centre + offset
+    }
+
+    /// Iterate over the hexes at exactly `radius` steps, counterclockwise from the south west corner
+    pub fn ring(&self, radius: u32) -> impl Iterator<Item = Self> + use<> {
+        let radius = radius as i32;
+        let start = *self + HexDirection::SouthWest.offset() * radius;
+        let centre = *self;
+        HexDirection::ALL
+            .into_iter()
+            .flat_map(move |direction| (0..radius).map(move |_| direction))
+            .scan(start, |hex, direction| {
+                let current = *hex;
+                *hex = hex.neighbor(direction);
+                Some(current)
+            })
+            .chain((radius == 0).then_some(centre))
+    }
+
+    /// Iterate over the hexes within `radius` steps, ring by ring from the centre
+    pub fn spiral(&self, radius: u32) -> impl Iterator<Item = Self> + use<> {
+        let centre = *self;
+        (0..=radius).flat_map(move |ring| centre.ring(ring))
+    }
+
+    /// Iterate over the hexes on the line between two hexes, both included
+    pub fn line_to(&self, other: Self) -> impl Iterator<Item = Self> + use<> {
+        let distance = self.distance(other);
+        // Nudge the line off the edges between hexes so ties round consistently
+        let from = Vec2::new(self.q as f32 + 1e-6, self.r as f32 + 1e-6);
+        let to = Vec2::new(other.q as f32 + 1e-6, other.r as f32 + 1e-6);
+        (0..=distance).map(move |step| {
+            let t = if distance == 0 {
+                0.0
+            } else {
+                step as f32 / distance as f32
+            };
+            let point = from.lerp(to, t);
+            Self::round(point.x, point.y)
+        })
+    }
+}
+
+impl From<GridPosition> for HexPosition {
+    fn from(position: GridPosition) -> Self {
+        Self::new(position.x, position.y)
+    }
+}
+
+impl From<HexPosition> for GridPosition {
+    fn from(hex: HexPosition) -> Self {
+        Self::new(hex.q, hex.r)
+    }
+}
+
+impl std::ops::Add<HexPosition> for HexPosition {
+    type Output = Self;
+
+    fn add(self, rhs: HexPosition) -> Self::Output {
+        Self::new(self.q + rhs.q, self.r + rhs.r)
+    }
+}
+
+impl std::ops::Sub<HexPosition> for HexPosition {
+    type Output = Self;
+
+    fn sub(self, rhs: HexPosition) -> Self::Output {
+        Self::new(self.q - rhs.q, self.r - rhs.r)
+    }
+}
+
+impl std::ops::Mul<i32> for HexPosition {
+    type Output = Self;
+
+    fn mul(self, rhs: i32) -> Self::Output {
+        Self::new(self.q * rhs, self.r * rhs)
+    }
+}
+
+/// One of the six directions of a hex grid, in counterclockwise order
+///
+/// The names match a pointy grid, where `East` runs along grid x.
+/// Directions double as the six rotations of an entity on a hex grid, see `HexFacing`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum HexDirection {
+    #[default]
+    East,
+    NorthEast,
+    NorthWest,
+    West,
+    SouthWest,
+    SouthEast,
+}
+
+impl HexDirection {
+    /// Every direction, counterclockwise from `East`
+    pub const ALL: [Self; 6] = [
+        Self::East,
+        Self::NorthEast,
+        Self::NorthWest,
+        Self::West,
+        Self::SouthWest,
+        Self::SouthEast,
+    ];
+
+    /// Get the position of the direction in `ALL`
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Get the axial offset to the neighbor in this direction
+    pub fn offset(&self) -> HexPosition {
+        match self {
+            Self::East => HexPosition::new(1, 0),
+            Self::NorthEast => HexPosition::new(0, 1),
+            Self::NorthWest => HexPosition::new(-1, 1),
+            Self::West => HexPosition::new(-1, 0),
+            Self::SouthWest => HexPosition::new(0, -1),
+            Self::SouthEast => HexPosition::new(1, -1),
+        }
+    }
+
+    /// Turn counterclockwise by a sixth
+    pub fn next(&self) -> Self {
+        Self::ALL[(self.index() + 1) % 6]
+    }
+
+    /// Turn clockwise by a sixth
+    pub fn previous(&self) -> Self {
+        Self::ALL[(self.index() + 5) % 6]
+    }
+
+    pub fn opposite(&self) -> Self {
+        Self::ALL[(self.index() + 3) % 6]
+    }
+}
+
+/// The six-way rotation of an entity on a hex grid
+///
+/// Placed entities with this component are rotated to face the direction instead of
+/// following their `GridRotation`. Used on grids with a hex `GridLattice`.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Component, Reflect, Serialize, Deserialize,
+)]
+#[reflect(Component, Default)]
+pub struct HexFacing(pub HexDirection);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_distance_and_rotation() {
+        let hex = HexPosition::new(2, -1);
+        assert_eq!(hex.cube(), IVec3::new(2, -1, -1));
+        assert_eq!(HexPosition::ORIGIN.distance(hex), 2);
+        assert_eq!(
+            HexPosition::from_cube(1, 2, -3).distance(HexPosition::ORIGIN),
+            3
+        );
+        assert!(
+            HexPosition::ORIGIN
+                .neighbors()
+                .all(|neighbor| neighbor.distance(HexPosition::ORIGIN) == 1)
+        );
+        // Six sixths make a full turn
+        let centre = HexPosition::new(1, 1);
+        assert_eq!(hex.rotate_around(centre, 6), hex);
+        assert_eq!(
+            HexDirection::East
+                .offset()
+                .rotate_around(HexPosition::ORIGIN, 1),
+            HexDirection::SouthEast.offset()
+        );
+        assert_eq!(HexDirection::West.next(), HexDirection::SouthWest);
+        assert_eq!(HexDirection::East.previous(), HexDirection::SouthEast);
+        assert_eq!(HexDirection::NorthEast.opposite(), HexDirection::SouthWest);
+    }
+
+    #[test]
+    fn test_hex_rings_and_lines() {
+        let centre = HexPosition::new(-1, 3);
+        assert_eq!(centre.ring(0).collect::<Vec<_>>(), vec![centre]);
+        let ring = centre.ring(2).collect::<Vec<_>>();
+        assert_eq!(ring.len(), 12);
+        assert!(ring.iter().all(|hex| hex.distance(centre) == 2));
+        assert_eq!(centre.spiral(2).count(), 19);
+
+        let line = HexPosition::ORIGIN
+            .line_to(HexPosition::new(3, -1))
+            .collect::<Vec<_>>();
+        assert_eq!(line.len(), 4);
+        assert_eq!(line[3], HexPosition::new(3, -1));
+        assert!(line.windows(2).all(|pair| pair[0].distance(pair[1]) == 1));
+    }
+
+    #[test]
+    fn test_hex_plane() {
+        for orientation in [HexOrientation::Pointy, HexOrientation::Flat] {
+            for hex in HexPosition::ORIGIN.spiral(3) {
+                let centre = orientation.to_plane(hex);
+                assert_eq!(orientation.from_plane(centre), hex);
+                // Points just inside the corners belong to the hex
+                let corner = Vec2::from_angle(orientation.corner_angle()) * 0.95;
+                assert_eq!(orientation.from_plane(centre + corner), hex);
+            }
+        }
+        assert_eq!(
+            HexOrientation::Pointy.to_plane(HexPosition::new(1, 0)),
+            Vec2::new(SQRT_3, 0.0)
+        );
+    }
+}
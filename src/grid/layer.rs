@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub mod prelude {
+    pub use super::GridLayer;
+}
+
+/// The layer of a grid an entity is placed on
+///
+/// Every layer has its own occupancy, so entities on different layers can share a cell.
+/// Entities without this component are placed on the default layer.
+/// Layers are plain ids, name them with constants such as `const FLOOR: GridLayer = GridLayer(1);`
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Component,
+    Reflect,
+    Serialize,
+    Deserialize,
+)]
+#[reflect(Component, Default)]
+pub struct GridLayer(pub u32);
+
+impl GridLayer {
+    /// The layer used by entities without a `GridLayer`
+    pub const DEFAULT: Self = Self(0);
+}
@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub mod prelude {
+    pub use super::{LineCell, LineKind};
+}
+
+use crate::prelude::*;
+
+/// How a line between two cells is traced
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum LineKind {
+    /// One cell per step along the longest axis, lines may cross corners diagonally
+    #[default]
+    Bresenham,
+    /// Every cell the segment between the cell centres touches, both sides of a crossed corner included
+    Supercover,
+}
+
+/// A cell along a line, with its occupant
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub struct LineCell {
+    pub position: GridPosition,
+    pub entry: Option<GridEntity>,
+}
+
+impl GridPosition {
+    /// Iterate over the cells of a line to another position, both ends included
+    pub fn line_to(&self, other: Self, kind: LineKind) -> impl Iterator<Item = Self> + use<> {
+        match kind {
+            LineKind::Bresenham => bresenham(*self, other),
+            LineKind::Supercover => supercover(*self, other),
+        }
+        .into_iter()
+    }
+}
+
+fn bresenham(from: GridPosition, to: GridPosition) -> Vec<GridPosition> {
+    let (dx, dy) = ((to.x - from.x).abs(), -(to.y - from.y).abs());
+    let (sx, sy) = ((to.x - from.x).signum(), (to.y - from.y).signum());
+    let mut error = dx + dy;
+    let mut current = from;
+    let mut cells = vec![current];
+    while current != to {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            current.x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            current.y += sy;
+        }
+        cells.push(current);
+    }
+    cells
+}
+
+fn supercover(from: GridPosition, to: GridPosition) -> Vec<GridPosition> {
+    let (nx, ny) = ((to.x - from.x).abs(), (to.y - from.y).abs());
+    let (sx, sy) = ((to.x - from.x).signum(), (to.y - from.y).signum());
+    let (mut ix, mut iy) = (0, 0);
+    let mut current = from;
+    let mut cells = vec![current];
+    while ix < nx || iy < ny {
+        // Compare where the segment leaves the cell, through a vertical or horizontal edge
+        let decision = (1 + 2 * ix) * ny - (1 + 2 * iy) * nx;
+        if decision == 0 {
+            // Through the corner, touching the cells on both sides
+            cells.push(GridPosition::new(current.x + sx, current.y));
+            cells.push(GridPosition::new(current.x, current.y + sy));
+            current.x += sx;
+            current.y += sy;
+            ix += 1;
+            iy += 1;
+        } else if decision < 0 {
+            current.x += sx;
+            ix += 1;
+        } else {
+            current.y += sy;
+            iy += 1;
+        }
+        cells.push(current);
+    }
+    cells
+}
+
+impl<S: GridStorage> Grid<S> {
+    /// Get the cells of a line between two positions and their occupants, both ends included
+    pub fn line(&self, from: GridPosition, to: GridPosition, kind: LineKind) -> Vec<LineCell> {
+        from.line_to(to, kind)
+            .map(|position| LineCell {
+                position,
+                entry: self.get(position),
+            })
+            .collect()
+    }
+
+    /// Get the first occupied cell along a line, leaving the start cell out
+    pub fn raycast(
+        &self,
+        from: GridPosition,
+        to: GridPosition,
+        kind: LineKind,
+    ) -> Option<Neighbor> {
+        self.raycast_filtered(from, to, kind, |_| true)
+    }
+
+    /// Get the first cell along a line whose occupant is hit, leaving the start cell out
+    pub fn raycast_filtered(
+        &self,
+        from: GridPosition,
+        to: GridPosition,
+        kind: LineKind,
+        hits: impl Fn(&Neighbor) -> bool,
+    ) -> Option<Neighbor> {
+        from.line_to(to, kind)
+            .skip(1)
+            .filter_map(|position| {
+                self.get(position)
+                    .map(|entry| Neighbor::new(position, entry))
+            })
+            .find(|neighbor| hits(neighbor))
+    }
+
+    /// Whether no occupant between two positions blocks sight, the ends being ignored
+    ///
+    /// `blocks` decides which occupants block sight, for instance walls but not units.
+    pub fn has_line_of_sight(
+        &self,
+        from: GridPosition,
+        to: GridPosition,
+        kind: LineKind,
+        blocks: impl Fn(&Neighbor) -> bool,
+    ) -> bool {
+        self.raycast_filtered(from, to, kind, blocks)
+            .is_none_or(|hit| hit.position == to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let from = GridPosition::new(0, 0);
+        let line = from
+            .line_to(GridPosition::new(4, 2), LineKind::Bresenham)
+            .collect::<Vec<_>>();
+        assert_eq!(line.len(), 5);
+        assert_eq!(line.last(), Some(&GridPosition::new(4, 2)));
+        assert!(
+            line.windows(2).all(
+                |step| (step[1].x - step[0].x).abs() <= 1 && (step[1].y - step[0].y).abs() <= 1
+            )
+        );
+
+        // The supercover line steps through edges only, and touches both sides of the corners
+        let cover = from
+            .line_to(GridPosition::new(2, 2), LineKind::Supercover)
+            .collect::<Vec<_>>();
+        assert_eq!(cover.len(), 7);
+        assert!(cover.contains(&GridPosition::new(1, 0)));
+        assert!(cover.contains(&GridPosition::new(0, 1)));
+        let cover = from
+            .line_to(GridPosition::new(-3, 1), LineKind::Supercover)
+            .collect::<Vec<_>>();
+        assert_eq!(cover, vec![
+            from,
+            GridPosition::new(-1, 0),
+            GridPosition::new(-2, 0),
+            GridPosition::new(-1, 1),
+            GridPosition::new(-2, 1),
+            GridPosition::new(-3, 1),
+        ]);
+        assert_eq!(
+            from.line_to(from, LineKind::Supercover).collect::<Vec<_>>(),
+            vec![from]
+        );
+    }
+
+    #[test]
+    fn test_raycast_and_line_of_sight() {
+        let mut grid = Grid::new();
+        let wall = Entity::from_raw(1);
+        let unit = Entity::from_raw(2);
+        grid.insert(GridPosition::new(2, 0), unit, Rotation::Up);
+        grid.insert(GridPosition::new(4, 0), wall, Rotation::Up);
+        let from = GridPosition::new(0, 0);
+        let to = GridPosition::new(6, 0);
+
+        let line = grid.line(from, to, LineKind::Bresenham);
+        assert_eq!(line.len(), 7);
+        assert_eq!(line[2].entry.map(|entry| entry.entity), Some(unit));
+        assert_eq!(
+            grid.raycast(from, to, LineKind::Bresenham)
+                .map(|hit| hit.entry.entity),
+            Some(unit)
+        );
+
+        let blocks = |neighbor: &Neighbor| neighbor.entry.entity == wall;
+        let kind = LineKind::Bresenham;
+        assert!(grid.has_line_of_sight(from, GridPosition::new(3, 0), kind, blocks));
+        assert!(!grid.has_line_of_sight(from, to, kind, blocks));
+        // The target itself doesn't block sight
+        assert!(grid.has_line_of_sight(from, GridPosition::new(4, 0), kind, blocks));
+    }
+}
@@ -1,27 +1,50 @@
+pub mod bitmap;
+pub mod bounded;
+pub mod chunk;
+pub mod direction;
 pub mod entity;
+pub mod footprint;
+pub mod fov;
+pub mod hex;
+pub mod layer;
+pub mod line;
+pub mod path;
 pub mod position;
+pub mod region;
+pub mod storage;
+pub mod voxel;
 
-use bevy::{prelude::*, utils::HashMap};
+use bevy::prelude::*;
 
 use crate::prelude::*;
 
 pub mod prelude {
     pub use super::Grid;
+    pub use super::bitmap::prelude::*;
+    pub use super::bounded::prelude::*;
+    pub use super::chunk::prelude::*;
+    pub use super::direction::prelude::*;
     pub use super::entity::prelude::*;
+    pub use super::footprint::prelude::*;
+    pub use super::fov::prelude::*;
+    pub use super::hex::prelude::*;
+    pub use super::layer::prelude::*;
+    pub use super::line::prelude::*;
+    pub use super::path::prelude::*;
     pub use super::position::prelude::*;
+    pub use super::region::prelude::*;
+    pub use super::storage::prelude::*;
+    pub use super::voxel::prelude::*;
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Grid {
-    pub data: HashMap<GridPosition, GridEntity>,
-}
-
-impl Default for Grid {
-    fn default() -> Self {
-        Self {
-            data: HashMap::default(),
-        }
-    }
+/// The occupancy of a grid
+///
+/// Cells are stored in chunks by default, see `BoundedGrid` for fixed-size grids.
+/// The cells are only reachable through the methods below, as the storages don't
+/// keep a position keyed map to expose.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Reflect)]
+pub struct Grid<S = ChunkedStorage> {
+    storage: S,
 }
 
 impl Grid {
@@ -29,41 +52,80 @@ impl Grid {
         Self::default()
     }
 
+    /// Get the free cell closest to the given position
+    /// This will return the position itself if it is free
+    pub fn nearest_free(&self, position: GridPosition) -> GridPosition {
+        self.find_nearest_free(position)
+            .expect("unbounded grids always have a free cell")
+    }
+}
+
+impl<S: GridStorage> Grid<S> {
+    /// Create a grid on top of the given storage
+    pub fn with_storage(storage: S) -> Self {
+        Self { storage }
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    pub fn storage_mut(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    /// Insert an entity, out of bounds positions are ignored
     pub fn insert(&mut self, position: GridPosition, entity: Entity, rotation: Rotation) {
-        self.data.insert(position, GridEntity { entity, rotation });
+        let _ = self.try_insert(position, entity, rotation);
+    }
+
+    /// Insert an entity, returning the previous occupant or an error if the position is out of bounds
+    pub fn try_insert(
+        &mut self,
+        position: GridPosition,
+        entity: Entity,
+        rotation: Rotation,
+    ) -> Result<Option<GridEntity>, GridOutOfBounds> {
+        self.storage
+            .insert(position, GridEntity::new(entity, rotation))
     }
 
     pub fn remove(&mut self, position: GridPosition) -> Option<GridEntity> {
-        self.data.remove(&position)
+        self.storage.remove(position)
     }
 
     pub fn get(&self, position: GridPosition) -> Option<GridEntity> {
-        eprintln!("GET position: {:?}", position);
-        eprintln!("GET data: {:?}", self.data);
-
-        let data = self.data.get(&position).copied();
-        eprintln!("GET NEW data: {:?}", data);
-        data
+        self.storage.get(position)
     }
 
     pub fn get_mut(&mut self, position: GridPosition) -> Option<&mut GridEntity> {
-        self.data.get_mut(&position)
+        self.storage.get_mut(position)
     }
 
     pub fn contains(&self, position: GridPosition) -> bool {
-        self.data.contains_key(&position)
+        self.get(position).is_some()
+    }
+
+    /// Whether the position can be stored in the grid
+    pub fn in_bounds(&self, position: GridPosition) -> bool {
+        self.storage.in_bounds(position)
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = (&GridPosition, &GridEntity)> {
-        self.data.iter()
+    /// Iterate the occupied cells, positions are yielded by value as the storage derives them
+    pub fn iter(&self) -> impl Iterator<Item = (GridPosition, &GridEntity)> {
+        self.storage.iter()
     }
 
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&GridPosition, &mut GridEntity)> {
-        self.data.iter_mut()
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (GridPosition, &mut GridEntity)> {
+        self.storage.iter_mut()
     }
 
     pub fn len(&self) -> usize {
-        self.data.len()
+        self.storage.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
     }
 
     pub fn fill(&mut self, entity: Entity, rotation: Rotation, radius: i32) {
@@ -76,9 +138,48 @@ impl Grid {
             }
         }
     }
+
+    /// Get the free cell closest to the given position, `None` if every cell in bounds is occupied
+    /// This will return the position itself if it is free
+    pub fn find_nearest_free(&self, position: GridPosition) -> Option<GridPosition> {
+        // Past this radius every ring is out of bounds
+        let max_radius = self.storage.bounds().map(|(min, max)| {
+            (position.x - min.x)
+                .abs()
+                .max((max.x - position.x).abs())
+                .max((position.y - min.y).abs())
+                .max((max.y - position.y).abs())
+        });
+        let mut radius: i32 = 0;
+        while max_radius.is_none_or(|max_radius| radius <= max_radius) {
+            let mut nearest: Option<(i32, GridPosition)> = None;
+            for x in -radius..=radius {
+                for y in -radius..=radius {
+                    // Only check the ring, the inner cells are occupied
+                    if x.abs() != radius && y.abs() != radius {
+                        continue;
+                    }
+                    let candidate = position + IVec2::new(x, y);
+                    let distance = x * x + y * y;
+                    if self.in_bounds(candidate)
+                        && !self.contains(candidate)
+                        && nearest.is_none_or(|(nearest, _)| distance < nearest)
+                    {
+                        nearest = Some((distance, candidate));
+                    }
+                }
+            }
+            if let Some((_, free)) = nearest {
+                return Some(free);
+            }
+            radius += 1;
+        }
+        None
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -92,7 +193,7 @@ mod tests {
 
         grid.insert(position, entity, rotation);
 
-        assert_eq!(grid.get(position), Some(GridEntity { entity, rotation }));
+        assert_eq!(grid.get(position), Some(GridEntity::new(entity, rotation)));
         assert_eq!(grid.contains(position), true);
         assert_eq!(grid.len(), 1);
 
@@ -118,10 +219,7 @@ mod tests {
 
         assert_eq!(
             grid.get(position),
-            Some(GridEntity {
-                entity,
-                rotation: Rotation::Right
-            })
+            Some(GridEntity::new(entity, Rotation::Right))
         );
     }
 
@@ -166,7 +264,7 @@ mod tests {
 
         grid.insert(position, entity, rotation);
 
-        assert_eq!(grid.get(position), Some(GridEntity { entity, rotation }));
+        assert_eq!(grid.get(position), Some(GridEntity::new(entity, rotation)));
     }
 
     #[test]
@@ -179,7 +277,7 @@ mod tests {
 
         grid.insert(position, entity, rotation);
 
-        assert_eq!(grid.get(position), Some(GridEntity { entity, rotation }));
+        assert_eq!(grid.get(position), Some(GridEntity::new(entity, rotation)));
     }
 
     #[test]
@@ -192,6 +290,57 @@ mod tests {
 
         grid.insert(position, entity, rotation);
 
-        assert_eq!(grid.remove(position), Some(GridEntity { entity, rotation }));
+        assert_eq!(
+            grid.remove(position),
+            Some(GridEntity::new(entity, rotation))
+        );
+    }
+
+    #[test]
+    fn test_grid_chunks() {
+        let mut grid = Grid::new();
+        let entity = Entity::PLACEHOLDER;
+        let rotation = Rotation::default();
+
+        grid.insert(GridPosition::new(0, 0), entity, rotation);
+        grid.insert(GridPosition::new(-1, 0), entity, rotation);
+        grid.insert(GridPosition::new(CHUNK_SIZE, 0), entity, rotation);
+        assert_eq!(grid.chunks().count(), 3);
+        assert_eq!(grid.iter().count(), 3);
+
+        let chunk = grid.remove_chunk(ChunkPosition::new(-1, 0)).unwrap();
+        assert_eq!(grid.len(), 2);
+        assert!(!grid.contains(GridPosition::new(-1, 0)));
+
+        grid.insert_chunk(chunk);
+        assert_eq!(grid.len(), 3);
+        assert!(grid.contains(GridPosition::new(-1, 0)));
+
+        // Emptied chunks are dropped
+        grid.remove(GridPosition::new(CHUNK_SIZE, 0));
+        assert!(grid.chunk(ChunkPosition::new(1, 0)).is_none());
+    }
+
+    #[test]
+    fn test_grid_nearest_free() {
+        let mut grid = Grid::new();
+
+        let entity = Entity::PLACEHOLDER;
+        let rotation = Rotation::default();
+
+        assert_eq!(
+            grid.nearest_free(GridPosition::new(0, 0)),
+            GridPosition::new(0, 0)
+        );
+
+        for x in -1..=1 {
+            grid.insert(GridPosition::new(x, 0), entity, rotation);
+        }
+        grid.insert(GridPosition::new(0, -1), entity, rotation);
+
+        assert_eq!(
+            grid.nearest_free(GridPosition::new(0, 0)),
+            GridPosition::new(0, 1)
+        );
     }
 }
@@ -0,0 +1,162 @@
+use std::collections::BinaryHeap;
+
+use bevy::utils::HashMap;
+
+use super::Scored;
+use crate::prelude::*;
+
+impl<S: GridStorage> Grid<S> {
+    /// Find the cheapest path between two cells with A*
+    ///
+    /// The start cell is never checked for walkability, the goal cell is.
+    /// Returns `None` if the goal can't be reached within `max_nodes` expansions.
+    pub fn find_path<W, C>(
+        &self,
+        start: GridPosition,
+        goal: GridPosition,
+        rules: &PathRules<W, C>,
+    ) -> Option<Path>
+    where
+        W: Fn(GridPosition, Option<GridEntity>) -> bool,
+        C: Fn(GridPosition, Option<GridEntity>) -> f32,
+    {
+        let mut open = BinaryHeap::new();
+        let mut costs = HashMap::<GridPosition, f32>::default();
+        let mut came_from = HashMap::<GridPosition, GridPosition>::default();
+
+        open.push(Scored {
+            score: rules.heuristic(start, goal),
+            position: start,
+        });
+        costs.insert(start, 0.0);
+
+        let mut expanded = 0;
+        while let Some(Scored { score, position }) = open.pop() {
+            let cost = costs[&position];
+            if position == goal {
+                return Some(Path {
+                    cells: reconstruct(&came_from, goal),
+                    cost,
+                });
+            }
+            // Skip entries superseded by a cheaper route
+            if score > cost + rules.heuristic(position, goal) {
+                continue;
+            }
+            expanded += 1;
+            if expanded > rules.max_nodes {
+                return None;
+            }
+
+            for (next, step) in self.path_successors(position, rules) {
+                let next_cost = cost + step;
+                if costs.get(&next).is_none_or(|&known| next_cost < known) {
+                    costs.insert(next, next_cost);
+                    came_from.insert(next, position);
+                    open.push(Scored {
+                        score: next_cost + rules.heuristic(next, goal),
+                        position: next,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Walk the came-from map back from the goal, returning the cells from the start
+pub(crate) fn reconstruct(
+    came_from: &HashMap<GridPosition, GridPosition>,
+    goal: GridPosition,
+) -> Vec<GridPosition> {
+    let mut cells = vec![goal];
+    let mut current = goal;
+    while let Some(&previous) = came_from.get(&current) {
+        cells.push(previous);
+        current = previous;
+    }
+    cells.reverse();
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+
+    fn walled() -> Grid {
+        let mut grid = Grid::new();
+        // A wall along x = 1 from y = -3 to 2
+        for y in -3..3 {
+            grid.insert(GridPosition::new(1, y), Entity::PLACEHOLDER, Rotation::Up);
+        }
+        grid
+    }
+
+    #[test]
+    fn test_find_path() {
+        let grid = walled();
+        let rules = PathRules::new(|_, entry: Option<GridEntity>| entry.is_none());
+
+        let path = grid
+            .find_path(GridPosition::new(0, 0), GridPosition::new(2, 0), &rules)
+            .unwrap();
+        assert_eq!(path.cells.first(), Some(&GridPosition::new(0, 0)));
+        assert_eq!(path.cells.last(), Some(&GridPosition::new(2, 0)));
+        // Around the end of the wall and back
+        assert_eq!(path.len(), 8);
+        assert_eq!(path.cost, 8.0);
+        assert!(path.cells.iter().all(|cell| !grid.contains(*cell)));
+
+        let path = grid
+            .find_path(GridPosition::new(0, 0), GridPosition::new(0, 0), &rules)
+            .unwrap();
+        assert!(path.is_empty());
+    }
+
+    #[test]
+    fn test_find_path_unreachable() {
+        let grid = walled();
+        let rules =
+            PathRules::new(|_, entry: Option<GridEntity>| entry.is_none()).with_max_nodes(100);
+        assert_eq!(
+            grid.find_path(GridPosition::new(0, 0), GridPosition::new(1, 0), &rules),
+            None
+        );
+
+        // Bounded walkability closes both ends of the wall
+        let rules = PathRules::new(|position: GridPosition, entry: Option<GridEntity>| {
+            entry.is_none() && (-3..3).contains(&position.y)
+        });
+        assert_eq!(
+            grid.find_path(GridPosition::new(0, 0), GridPosition::new(2, 0), &rules),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_path_cost_and_diagonals() {
+        let grid = Grid::new();
+        let rules = PathRules::new(|_, _| true).with_connectivity(Connectivity::Eight);
+        let path = grid
+            .find_path(GridPosition::new(0, 0), GridPosition::new(3, 3), &rules)
+            .unwrap();
+        assert_eq!(path.len(), 3);
+        assert!((path.cost - 3.0 * std::f32::consts::SQRT_2).abs() < 1e-4);
+
+        // An expensive column is walked around
+        let rules = PathRules::new(|_, _| true).with_cost(|position: GridPosition, _| {
+            if position.x == 1 && position.y != 2 {
+                10.0
+            } else {
+                1.0
+            }
+        });
+        let path = grid
+            .find_path(GridPosition::new(0, 0), GridPosition::new(2, 0), &rules)
+            .unwrap();
+        assert!(path.cells.contains(&GridPosition::new(1, 2)));
+        assert_eq!(path.cost, 6.0);
+    }
+}
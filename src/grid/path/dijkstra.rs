@@ -0,0 +1,224 @@
+use std::{collections::BinaryHeap, f32::consts::SQRT_2};
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{Scored, astar::reconstruct};
+use crate::prelude::*;
+
+pub mod prelude {
+    pub use super::FlowField;
+}
+
+/// The cheapest route from every reachable cell to the nearest goal
+#[derive(Debug, Default, Clone, PartialEq, Reflect)]
+#[reflect(Default)]
+pub struct FlowField {
+    /// The cost of reaching the nearest goal from each cell
+    pub costs: HashMap<GridPosition, f32>,
+    /// The next cell on the way to the nearest goal, goals have none
+    pub next: HashMap<GridPosition, GridPosition>,
+}
+
+impl FlowField {
+    /// Get the cost of reaching the nearest goal from a cell, `None` if it is unreachable
+    pub fn cost(&self, position: GridPosition) -> Option<f32> {
+        self.costs.get(&position).copied()
+    }
+
+    /// Get the next cell on the way to the nearest goal
+    pub fn next_step(&self, position: GridPosition) -> Option<GridPosition> {
+        self.next.get(&position).copied()
+    }
+
+    /// Get the direction of the next step on the way to the nearest goal
+    pub fn direction(&self, position: GridPosition) -> Option<IVec2> {
+        self.next_step(position)
+            .map(|next| IVec2::new(next.x - position.x, next.y - position.y))
+    }
+
+    /// Whether a goal can be reached from a cell
+    pub fn is_reachable(&self, position: GridPosition) -> bool {
+        self.costs.contains_key(&position)
+    }
+}
+
+impl<S: GridStorage> Grid<S> {
+    /// Find the cheapest path between two cells with Dijkstra's algorithm
+    ///
+    /// Unlike `find_path` this makes no assumption on the costs, which may be below 1.
+    /// Returns `None` if the search stops after `max_nodes` expansions before settling the goal.
+    pub fn find_path_dijkstra<W, C>(
+        &self,
+        start: GridPosition,
+        goal: GridPosition,
+        rules: &PathRules<W, C>,
+    ) -> Option<Path>
+    where
+        W: Fn(GridPosition, Option<GridEntity>) -> bool,
+        C: Fn(GridPosition, Option<GridEntity>) -> f32,
+    {
+        let mut came_from = HashMap::default();
+        let (costs, reached) = self.dijkstra([start], rules, Some(goal), false, |from, to| {
+            came_from.insert(to, from);
+        });
+        // The cost of a goal that was never popped may not be the cheapest one yet
+        if !reached {
+            return None;
+        }
+        let cost = *costs.get(&goal)?;
+        Some(Path {
+            cells: reconstruct(&came_from, goal),
+            cost,
+        })
+    }
+
+    /// Build a flow field leading every reachable cell to its nearest goal
+    ///
+    /// Moves are reversed, so the costs are those of walking from a cell to the goal.
+    /// The search stops after `max_nodes` expansions, leaving the farthest cells out.
+    pub fn flow_field<W, C>(
+        &self,
+        goals: impl IntoIterator<Item = GridPosition>,
+        rules: &PathRules<W, C>,
+    ) -> FlowField
+    where
+        W: Fn(GridPosition, Option<GridEntity>) -> bool,
+        C: Fn(GridPosition, Option<GridEntity>) -> f32,
+    {
+        let mut next = HashMap::default();
+        let (costs, _) = self.dijkstra(goals, rules, None, true, |from, to| {
+            next.insert(to, from);
+        });
+        FlowField { costs, next }
+    }
+
+    /// Run Dijkstra's algorithm from the sources, reporting every improved edge
+    ///
+    /// Also returns whether the target was reached, its cost is final only then.
+    /// Reversed searches charge each move the cost of the cell it leaves, which is the cell
+    /// entered when walking back towards the sources.
+    fn dijkstra<W, C>(
+        &self,
+        sources: impl IntoIterator<Item = GridPosition>,
+        rules: &PathRules<W, C>,
+        target: Option<GridPosition>,
+        reverse: bool,
+        mut improved: impl FnMut(GridPosition, GridPosition),
+    ) -> (HashMap<GridPosition, f32>, bool)
+    where
+        W: Fn(GridPosition, Option<GridEntity>) -> bool,
+        C: Fn(GridPosition, Option<GridEntity>) -> f32,
+    {
+        let mut open = BinaryHeap::new();
+        let mut costs = HashMap::<GridPosition, f32>::default();
+        for source in sources {
+            costs.insert(source, 0.0);
+            open.push(Scored {
+                score: 0.0,
+                position: source,
+            });
+        }
+
+        let mut expanded = 0;
+        while let Some(Scored { score, position }) = open.pop() {
+            if Some(position) == target {
+                return (costs, true);
+            }
+            // Skip entries superseded by a cheaper route
+            if score > costs[&position] {
+                continue;
+            }
+            expanded += 1;
+            if expanded > rules.max_nodes {
+                break;
+            }
+
+            let leave_cost = reverse.then(|| (rules.cost)(position, self.get(position)));
+            for (next, step) in self.path_successors(position, rules) {
+                let step = match leave_cost {
+                    Some(cost) if next.x != position.x && next.y != position.y => cost * SQRT_2,
+                    Some(cost) => cost,
+                    None => step,
+                };
+                let next_cost = score + step;
+                if costs.get(&next).is_none_or(|&known| next_cost < known) {
+                    costs.insert(next, next_cost);
+                    improved(position, next);
+                    open.push(Scored {
+                        score: next_cost,
+                        position: next,
+                    });
+                }
+            }
+        }
+        (costs, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_path_dijkstra() {
+        let mut grid = Grid::new();
+        for y in -3..3 {
+            grid.insert(GridPosition::new(1, y), Entity::PLACEHOLDER, Rotation::Up);
+        }
+        let rules = PathRules::new(|_, entry: Option<GridEntity>| entry.is_none());
+        let dijkstra = grid
+            .find_path_dijkstra(GridPosition::new(0, 0), GridPosition::new(2, 0), &rules)
+            .unwrap();
+        let astar = grid
+            .find_path(GridPosition::new(0, 0), GridPosition::new(2, 0), &rules)
+            .unwrap();
+        assert_eq!(dijkstra.cost, astar.cost);
+        assert_eq!(dijkstra.len(), astar.len());
+    }
+
+    #[test]
+    fn test_find_path_dijkstra_max_nodes() {
+        let grid = Grid::new();
+        let goal = GridPosition::new(1, 1);
+        let rules = PathRules::new(|_, _| true)
+            .with_cost(|position, _| if position == goal { 10.0 } else { 1.0 });
+        // Stepping around the diagonal is cheaper than the first route found to the goal
+        let path = grid
+            .find_path_dijkstra(GridPosition::new(0, 0), goal, &rules)
+            .unwrap();
+        assert_eq!(path.cost, 11.0);
+        let rules = rules.with_max_nodes(1);
+        assert!(
+            grid.find_path_dijkstra(GridPosition::new(0, 0), goal, &rules)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_flow_field() {
+        let mut grid = Grid::new();
+        grid.insert(GridPosition::new(1, 0), Entity::PLACEHOLDER, Rotation::Up);
+        let rules = PathRules::new(|position: GridPosition, entry: Option<GridEntity>| {
+            entry.is_none() && position.x.abs() <= 3 && position.y.abs() <= 3
+        });
+        let field = grid.flow_field([GridPosition::new(0, 0)], &rules);
+
+        assert_eq!(field.cost(GridPosition::new(0, 0)), Some(0.0));
+        assert_eq!(field.next_step(GridPosition::new(0, 0)), None);
+        assert_eq!(
+            field.direction(GridPosition::new(-2, 0)),
+            Some(IVec2::new(1, 0))
+        );
+        // The blocked cell is routed around
+        assert!(!field.is_reachable(GridPosition::new(1, 0)));
+        assert_eq!(field.cost(GridPosition::new(2, 0)), Some(4.0));
+        assert!(!field.is_reachable(GridPosition::new(4, 0)));
+
+        // Following the field always ends on the goal
+        let mut position = GridPosition::new(3, -3);
+        while let Some(next) = field.next_step(position) {
+            position = next;
+        }
+        assert_eq!(position, GridPosition::new(0, 0));
+    }
+}
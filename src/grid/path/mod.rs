@@ -0,0 +1,263 @@
+use std::{cmp::Ordering, f32::consts::SQRT_2};
+
+use bevy::prelude::*;
+
+use crate::prelude::*;
+
+pub mod astar;
+pub mod dijkstra;
+pub mod task;
+
+pub mod prelude {
+    pub use super::dijkstra::prelude::*;
+    pub use super::task::prelude::*;
+    pub use super::{Connectivity, CornerCutting, Path, PathRules};
+}
+
+/// The moves allowed between cells
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect)]
+pub enum Connectivity {
+    /// Only move to cardinal neighbors
+    #[default]
+    Four,
+    /// Move to cardinal and ordinal neighbors
+    Eight,
+}
+
+/// When a diagonal move may pass the corner of a blocked cell
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect)]
+pub enum CornerCutting {
+    /// Diagonal moves are always allowed
+    Allow,
+    /// Diagonal moves are allowed if at least one of the two adjacent cardinal cells is walkable
+    IfEitherWalkable,
+    /// Diagonal moves are only allowed if both adjacent cardinal cells are walkable
+    #[default]
+    Never,
+}
+
+/// The cost of entering a cell, used when no cost callback is given
+pub fn unit_cost(_: GridPosition, _: Option<GridEntity>) -> f32 {
+    1.0
+}
+
+/// The rules of a path search
+///
+/// Both callbacks receive the cell and its occupant, `None` for empty cells.
+/// The cost callback returns the cost of entering a cell and should be at least 1,
+/// which is what the A* heuristic assumes.
+#[derive(Debug, Clone)]
+pub struct PathRules<W, C = fn(GridPosition, Option<GridEntity>) -> f32> {
+    /// Whether a cell can be entered
+    pub walkable: W,
+    /// The cost of entering a cell
+    pub cost: C,
+    /// The moves allowed between cells
+    pub connectivity: Connectivity,
+    /// When diagonal moves may cut corners
+    pub corner_cutting: CornerCutting,
+    /// The maximum number of cells to expand before giving up
+    pub max_nodes: usize,
+}
+
+impl<W> PathRules<W>
+where
+    W: Fn(GridPosition, Option<GridEntity>) -> bool,
+{
+    /// Create rules with the given walkability, a unit cost and four-way moves
+    pub fn new(walkable: W) -> Self {
+        Self {
+            walkable,
+            cost: unit_cost,
+            connectivity: Connectivity::default(),
+            corner_cutting: CornerCutting::default(),
+            max_nodes: 65_536,
+        }
+    }
+}
+
+impl<W, C> PathRules<W, C>
+where
+    W: Fn(GridPosition, Option<GridEntity>) -> bool,
+    C: Fn(GridPosition, Option<GridEntity>) -> f32,
+{
+    /// Use the given cost callback
+    pub fn with_cost<D>(self, cost: D) -> PathRules<W, D>
+    where
+        D: Fn(GridPosition, Option<GridEntity>) -> f32,
+    {
+        PathRules {
+            walkable: self.walkable,
+            cost,
+            connectivity: self.connectivity,
+            corner_cutting: self.corner_cutting,
+            max_nodes: self.max_nodes,
+        }
+    }
+
+    /// Use the given connectivity
+    pub fn with_connectivity(mut self, connectivity: Connectivity) -> Self {
+        self.connectivity = connectivity;
+        self
+    }
+
+    /// Use the given corner cutting rule
+    pub fn with_corner_cutting(mut self, corner_cutting: CornerCutting) -> Self {
+        self.corner_cutting = corner_cutting;
+        self
+    }
+
+    /// Give up after expanding the given number of cells
+    pub fn with_max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    /// The estimated cost between two cells, never more than the real cost for unit costs
+    pub(crate) fn heuristic(&self, from: GridPosition, to: GridPosition) -> f32 {
+        let dx = (from.x - to.x).abs() as f32;
+        let dy = (from.y - to.y).abs() as f32;
+        match self.connectivity {
+            Connectivity::Four => dx + dy,
+            Connectivity::Eight => dx.max(dy) + (SQRT_2 - 1.0) * dx.min(dy),
+        }
+    }
+}
+
+/// A path between two cells
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub struct Path {
+    /// The cells of the path, from the start to the goal, both included
+    pub cells: Vec<GridPosition>,
+    /// The total cost of the path
+    pub cost: f32,
+}
+
+impl Path {
+    /// The number of moves in the path
+    pub fn len(&self) -> usize {
+        self.cells.len().saturating_sub(1)
+    }
+
+    /// Whether the path has no moves, meaning the start is the goal
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<S: GridStorage> Grid<S> {
+    /// Get the cells reachable in one move from a position and the cost of each move
+    ///
+    /// The moves follow the cardinal neighbors, and the ordinal neighbors for eight-way connectivity.
+    pub fn path_successors<W, C>(
+        &self,
+        position: GridPosition,
+        rules: &PathRules<W, C>,
+    ) -> Vec<(GridPosition, f32)>
+    where
+        W: Fn(GridPosition, Option<GridEntity>) -> bool,
+        C: Fn(GridPosition, Option<GridEntity>) -> f32,
+    {
+        let cardinal = self.cardinal_cells(position);
+        let cardinal = [
+            (IVec2::new(0, 1), cardinal.north),
+            (IVec2::new(1, 0), cardinal.east),
+            (IVec2::new(0, -1), cardinal.south),
+            (IVec2::new(-1, 0), cardinal.west),
+        ];
+        let walkable = |offset: IVec2, neighbor: &Option<Neighbor>| {
+            (rules.walkable)(position + offset, neighbor.as_ref().map(|n| n.entry))
+        };
+
+        let mut successors = Vec::with_capacity(8);
+        for (offset, neighbor) in &cardinal {
+            if walkable(*offset, neighbor) {
+                let entry = neighbor.as_ref().map(|neighbor| neighbor.entry);
+                successors.push((position + *offset, (rules.cost)(position + *offset, entry)));
+            }
+        }
+        if rules.connectivity == Connectivity::Four {
+            return successors;
+        }
+
+        let ordinal = self.ordinal_cells(position);
+        let ordinal = [
+            (IVec2::new(-1, 1), ordinal.north_west),
+            (IVec2::new(1, 1), ordinal.north_east),
+            (IVec2::new(1, -1), ordinal.south_east),
+            (IVec2::new(-1, -1), ordinal.south_west),
+        ];
+        for (offset, neighbor) in &ordinal {
+            if !walkable(*offset, neighbor) {
+                continue;
+            }
+            // The two cardinal cells the diagonal move passes between
+            let side = |side: IVec2| {
+                cardinal
+                    .iter()
+                    .find(|(cardinal_offset, _)| *cardinal_offset == side)
+                    .is_some_and(|(offset, neighbor)| walkable(*offset, neighbor))
+            };
+            let (horizontal, vertical) = (side(offset.with_y(0)), side(offset.with_x(0)));
+            let allowed = match rules.corner_cutting {
+                CornerCutting::Allow => true,
+                CornerCutting::IfEitherWalkable => horizontal || vertical,
+                CornerCutting::Never => horizontal && vertical,
+            };
+            if allowed {
+                let entry = neighbor.as_ref().map(|neighbor| neighbor.entry);
+                successors.push((
+                    position + *offset,
+                    (rules.cost)(position + *offset, entry) * SQRT_2,
+                ));
+            }
+        }
+        successors
+    }
+}
+
+/// A cell in an open set, ordered so that a `BinaryHeap` pops the lowest score first
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Scored {
+    pub score: f32,
+    pub position: GridPosition,
+}
+
+impl Eq for Scored {}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.score.total_cmp(&self.score)
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_successors() {
+        let mut grid = Grid::new();
+        // A wall north of the origin
+        grid.insert(GridPosition::new(0, 1), Entity::PLACEHOLDER, Rotation::Up);
+
+        let rules = PathRules::new(|_, entry: Option<GridEntity>| entry.is_none());
+        let successors = grid.path_successors(GridPosition::new(0, 0), &rules);
+        assert_eq!(successors.len(), 3);
+
+        let rules = rules.with_connectivity(Connectivity::Eight);
+        let successors = grid.path_successors(GridPosition::new(0, 0), &rules);
+        // The two northern diagonals are left out, as they would cut the corner of the wall
+        assert_eq!(successors.len(), 5);
+
+        let rules = rules.with_corner_cutting(CornerCutting::IfEitherWalkable);
+        let successors = grid.path_successors(GridPosition::new(0, 0), &rules);
+        assert_eq!(successors.len(), 7);
+    }
+}
@@ -0,0 +1,46 @@
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+
+use crate::prelude::*;
+
+pub mod prelude {
+    pub use super::find_path_task;
+}
+
+/// Find a path with A* on the async compute task pool
+///
+/// The grid is moved into the task, clone it to keep using it meanwhile.
+/// Poll the task with `bevy::tasks::futures_lite::future::poll_once` or `block_on`.
+pub fn find_path_task<S, W, C>(
+    grid: Grid<S>,
+    start: GridPosition,
+    goal: GridPosition,
+    rules: PathRules<W, C>,
+) -> Task<Option<Path>>
+where
+    S: GridStorage + Send + 'static,
+    W: Fn(GridPosition, Option<GridEntity>) -> bool + Send + Sync + 'static,
+    C: Fn(GridPosition, Option<GridEntity>) -> f32 + Send + Sync + 'static,
+{
+    AsyncComputeTaskPool::get().spawn(async move { grid.find_path(start, goal, &rules) })
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::tasks::{TaskPool, block_on};
+
+    use super::*;
+
+    #[test]
+    fn test_find_path_task() {
+        AsyncComputeTaskPool::get_or_init(TaskPool::default);
+        let grid = Grid::new();
+        let rules = PathRules::new(|_, _| true);
+        let path = block_on(find_path_task(
+            grid,
+            GridPosition::new(0, 0),
+            GridPosition::new(2, 3),
+            rules,
+        ));
+        assert_eq!(path.map(|path| path.len()), Some(5));
+    }
+}
@@ -1,10 +1,14 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 pub mod prelude {
     pub use super::GridPosition;
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Component)]
+use crate::prelude::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
 pub struct GridPosition {
     pub x: i32,
     pub y: i32,
@@ -14,6 +18,26 @@ impl GridPosition {
     pub fn new(x: i32, y: i32) -> Self {
         Self { x, y }
     }
+
+    /// Get the cell next to this one in a direction
+    pub fn neighbor(&self, direction: Direction8) -> Self {
+        *self + direction.to_ivec2()
+    }
+
+    /// Turn the position around a pivot, like the rotation turns grid y towards grid x
+    pub fn rotate_around(&self, pivot: Self, rotation: Rotation) -> Self {
+        pivot + rotation.rotate_offset(IVec2::from(*self - pivot))
+    }
+
+    /// Mirror the position across the column of a pivot, flipping x
+    pub fn mirror_x(&self, pivot: Self) -> Self {
+        Self::new(2 * pivot.x - self.x, self.y)
+    }
+
+    /// Mirror the position across the row of a pivot, flipping y
+    pub fn mirror_y(&self, pivot: Self) -> Self {
+        Self::new(self.x, 2 * pivot.y - self.y)
+    }
 }
 
 impl From<IVec2> for GridPosition {
@@ -22,6 +46,12 @@ impl From<IVec2> for GridPosition {
     }
 }
 
+impl From<GridPosition> for IVec2 {
+    fn from(position: GridPosition) -> Self {
+        IVec2::new(position.x, position.y)
+    }
+}
+
 impl std::ops::Add<GridPosition> for GridPosition {
     type Output = Self;
 
@@ -44,6 +74,17 @@ impl std::ops::Add<IVec2> for GridPosition {
     }
 }
 
+impl std::ops::Sub<GridPosition> for GridPosition {
+    type Output = Self;
+
+    fn sub(self, rhs: GridPosition) -> Self::Output {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,4 +104,24 @@ mod tests {
         let result = pos + vec;
         assert_eq!(result, GridPosition::new(4, 6));
     }
+
+    #[test]
+    fn test_rotate_and_mirror() {
+        let pivot = GridPosition::new(1, 1);
+        let pos = GridPosition::new(1, 3);
+        assert_eq!(
+            pos.rotate_around(pivot, Rotation::Right),
+            GridPosition::new(3, 1)
+        );
+        assert_eq!(
+            pos.rotate_around(pivot, Rotation::Down),
+            GridPosition::new(1, -1)
+        );
+        assert_eq!(pos.mirror_y(pivot), GridPosition::new(1, -1));
+        assert_eq!(
+            GridPosition::new(4, 0).mirror_x(pivot),
+            GridPosition::new(-2, 0)
+        );
+        assert_eq!(pos.neighbor(Direction8::SouthWest), GridPosition::new(0, 2));
+    }
 }
@@ -0,0 +1,410 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+pub mod prelude {
+    pub use super::{GridRegions, Region, RegionId};
+}
+
+use crate::prelude::*;
+
+impl<S: GridStorage> Grid<S> {
+    /// Get the occupied neighbors of a cell, cardinal first
+    pub fn get_connected_neighbors(
+        &self,
+        position: GridPosition,
+        connectivity: Connectivity,
+    ) -> Vec<Neighbor> {
+        let cardinal = self.cardinal_cells(position);
+        let mut neighbors = Direction8::CARDINAL
+            .iter()
+            .filter_map(|direction| cardinal[*direction].clone())
+            .collect::<Vec<_>>();
+        if connectivity == Connectivity::Eight {
+            let ordinal = self.ordinal_cells(position);
+            neighbors.extend(
+                Direction8::ORDINAL
+                    .iter()
+                    .filter_map(|direction| ordinal[*direction].clone()),
+            );
+        }
+        neighbors
+    }
+
+    /// Get the cells connected to a start cell whose occupants match a predicate, start included
+    ///
+    /// The cells are returned in breadth-first order, empty if the start cell doesn't match.
+    pub fn flood_fill(
+        &self,
+        start: GridPosition,
+        connectivity: Connectivity,
+        predicate: impl Fn(&GridEntity) -> bool,
+    ) -> Vec<Neighbor> {
+        match self.get(start) {
+            Some(entry) if predicate(&entry) => {
+                self.flood(Neighbor::new(start, entry), connectivity, |_, next| {
+                    predicate(&next.entry)
+                })
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get the cells reached from a start cell, stepping to the neighbors `connects` accepts
+    fn flood(
+        &self,
+        start: Neighbor,
+        connectivity: Connectivity,
+        connects: impl Fn(&Neighbor, &Neighbor) -> bool,
+    ) -> Vec<Neighbor> {
+        let mut visited = HashSet::from([start.position]);
+        let mut queue = VecDeque::from([start]);
+        let mut filled = Vec::new();
+        while let Some(current) = queue.pop_front() {
+            for next in self.get_connected_neighbors(current.position, connectivity) {
+                if !visited.contains(&next.position) && connects(&current, &next) {
+                    visited.insert(next.position);
+                    queue.push_back(next);
+                }
+            }
+            filled.push(current);
+        }
+        filled
+    }
+}
+
+/// The identifier of a region, kept by the region while it is updated
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
+pub struct RegionId(pub u32);
+
+/// A group of connected cells
+#[derive(Debug, Clone, PartialEq, Eq, Reflect)]
+pub struct Region {
+    pub id: RegionId,
+    /// The cells of the region, in no particular order
+    pub cells: Vec<GridPosition>,
+    /// The corner of the bounding box with the lowest coordinates
+    pub min: GridPosition,
+    /// The corner of the bounding box with the highest coordinates
+    pub max: GridPosition,
+}
+
+impl Region {
+    fn new(id: RegionId, cells: Vec<GridPosition>) -> Self {
+        let (min, max) = cells.iter().fold((cells[0], cells[0]), |(min, max), cell| {
+            (
+                GridPosition::new(min.x.min(cell.x), min.y.min(cell.y)),
+                GridPosition::new(max.x.max(cell.x), max.y.max(cell.y)),
+            )
+        });
+        Self {
+            id,
+            cells,
+            min,
+            max,
+        }
+    }
+
+    /// Get the number of cells of the region
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+/// The connected regions of a grid
+///
+/// Two neighboring occupied cells are in the same region if `same_region` accepts their
+/// occupants, for instance entities of the same kind, or with `label_tagged` if both match a
+/// `TagFilter`. Call `update` with the cells that changed to relabel the regions around them only.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Reflect)]
+pub struct GridRegions {
+    connectivity: Connectivity,
+    labels: HashMap<GridPosition, RegionId>,
+    regions: HashMap<RegionId, Region>,
+    next_id: u32,
+}
+
+impl GridRegions {
+    /// Label every occupied cell of a grid
+    pub fn label<S: GridStorage>(
+        grid: &Grid<S>,
+        connectivity: Connectivity,
+        same_region: impl Fn(&GridEntity, &GridEntity) -> bool,
+    ) -> Self {
+        let mut regions = Self {
+            connectivity,
+            ..default()
+        };
+        let cells = grid
+            .iter()
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+        regions.fill(grid, cells, &HashMap::default(), |_| true, same_region);
+        regions
+    }
+
+    /// Label the occupied cells of a grid whose tags match a filter, ignoring the others
+    ///
+    /// Neighboring matching cells are in the same region whatever their other tags.
+    pub fn label_tagged<S: GridStorage>(
+        grid: &Grid<S>,
+        connectivity: Connectivity,
+        filter: impl Into<TagFilter>,
+    ) -> Self {
+        let mut regions = Self {
+            connectivity,
+            ..default()
+        };
+        regions.update_tagged(grid, grid.iter().map(|(position, _)| position), filter);
+        regions
+    }
+
+    /// Relabel the regions around cells whose occupant was added, removed or changed
+    ///
+    /// Regions keep their id when they grow, shrink or merge. A region split in parts keeps its
+    /// id for one part and the others get new ids.
+    pub fn update<S: GridStorage>(
+        &mut self,
+        grid: &Grid<S>,
+        changed: impl IntoIterator<Item = GridPosition>,
+        same_region: impl Fn(&GridEntity, &GridEntity) -> bool,
+    ) {
+        let (seeds, previous) = self.dissolve(changed);
+        self.fill(grid, seeds, &previous, |_| true, same_region);
+    }
+
+    /// Relabel the regions of tag-matching cells around cells that changed, see `label_tagged`
+    pub fn update_tagged<S: GridStorage>(
+        &mut self,
+        grid: &Grid<S>,
+        changed: impl IntoIterator<Item = GridPosition>,
+        filter: impl Into<TagFilter>,
+    ) {
+        let filter = filter.into();
+        let (seeds, previous) = self.dissolve(changed);
+        self.fill(
+            grid,
+            seeds,
+            &previous,
+            |entry| filter.matches_entry(entry),
+            |_, _| true,
+        );
+    }
+
+    /// Remove the regions of the changed cells and of their neighbors
+    ///
+    /// Returns the cells to flood again and the previous region of the removed cells.
+    fn dissolve(
+        &mut self,
+        changed: impl IntoIterator<Item = GridPosition>,
+    ) -> (Vec<GridPosition>, HashMap<GridPosition, RegionId>) {
+        // Dissolve the regions of the changed cells and of their neighbors
+        let mut dissolved = HashSet::new();
+        let mut seeds = Vec::new();
+        for position in changed {
+            seeds.push(position);
+            let neighbors = match self.connectivity {
+                Connectivity::Four => &Direction8::CARDINAL[..],
+                Connectivity::Eight => &Direction8::ALL[..],
+            };
+            for cell in std::iter::once(position).chain(
+                neighbors
+                    .iter()
+                    .map(|direction| position.neighbor(*direction)),
+            ) {
+                if let Some(id) = self.labels.get(&cell) {
+                    dissolved.insert(*id);
+                }
+            }
+        }
+        let mut previous = HashMap::default();
+        for id in dissolved {
+            let Some(region) = self.regions.remove(&id) else {
+                continue;
+            };
+            for cell in region.cells {
+                self.labels.remove(&cell);
+                previous.insert(cell, id);
+                seeds.push(cell);
+            }
+        }
+        (seeds, previous)
+    }
+
+    /// Flood the unlabeled occupied seeds `includes` accepts into regions, reusing the previous
+    /// ids of their cells
+    fn fill<S: GridStorage>(
+        &mut self,
+        grid: &Grid<S>,
+        seeds: Vec<GridPosition>,
+        previous: &HashMap<GridPosition, RegionId>,
+        includes: impl Fn(&GridEntity) -> bool,
+        same_region: impl Fn(&GridEntity, &GridEntity) -> bool,
+    ) {
+        for seed in seeds {
+            if self.labels.contains_key(&seed) {
+                continue;
+            }
+            let Some(entry) = grid.get(seed).filter(|entry| includes(entry)) else {
+                continue;
+            };
+            let cells = grid
+                .flood(
+                    Neighbor::new(seed, entry),
+                    self.connectivity,
+                    |current, next| {
+                        includes(&next.entry) && same_region(&current.entry, &next.entry)
+                    },
+                )
+                .into_iter()
+                .map(|neighbor| neighbor.position)
+                .collect::<Vec<_>>();
+            let reused = cells
+                .iter()
+                .filter_map(|cell| previous.get(cell).copied())
+                .find(|id| !self.regions.contains_key(id));
+            let id = reused.unwrap_or_else(|| {
+                self.next_id += 1;
+                RegionId(self.next_id)
+            });
+            for cell in &cells {
+                self.labels.insert(*cell, id);
+            }
+            self.regions.insert(id, Region::new(id, cells));
+        }
+    }
+
+    /// Get the region of a cell, `None` for empty cells
+    pub fn region_at(&self, position: GridPosition) -> Option<&Region> {
+        self.regions.get(self.labels.get(&position)?)
+    }
+
+    /// Get the id of the region of a cell, `None` for empty cells
+    pub fn id_at(&self, position: GridPosition) -> Option<RegionId> {
+        self.labels.get(&position).copied()
+    }
+
+    pub fn get(&self, id: RegionId) -> Option<&Region> {
+        self.regions.get(&id)
+    }
+
+    /// Iterate over the regions, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &Region> {
+        self.regions.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Entity = Entity::from_raw(1);
+    const BLUE: Entity = Entity::from_raw(2);
+
+    fn same_kind(a: &GridEntity, b: &GridEntity) -> bool {
+        a.entity == b.entity
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        let mut grid = Grid::new();
+        for x in 0..3 {
+            grid.insert(GridPosition::new(x, 0), RED, Rotation::Up);
+        }
+        grid.insert(GridPosition::new(3, 1), RED, Rotation::Up);
+        grid.insert(GridPosition::new(1, 1), BLUE, Rotation::Up);
+
+        let is_red = |entry: &GridEntity| entry.entity == RED;
+        let four = grid.flood_fill(GridPosition::new(0, 0), Connectivity::Four, is_red);
+        assert_eq!(four.len(), 3);
+        assert_eq!(four[0].position, GridPosition::new(0, 0));
+        let eight = grid.flood_fill(GridPosition::new(0, 0), Connectivity::Eight, is_red);
+        assert_eq!(eight.len(), 4);
+        assert!(
+            grid.flood_fill(GridPosition::new(1, 1), Connectivity::Eight, is_red)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_region_labeling() {
+        let mut grid = Grid::new();
+        for x in 0..5 {
+            grid.insert(GridPosition::new(x, 0), RED, Rotation::Up);
+        }
+        grid.insert(GridPosition::new(0, 1), BLUE, Rotation::Up);
+        grid.insert(GridPosition::new(1, 1), BLUE, Rotation::Up);
+
+        let mut regions = GridRegions::label(&grid, Connectivity::Four, same_kind);
+        assert_eq!(regions.len(), 2);
+        let red = regions.region_at(GridPosition::new(4, 0)).unwrap().clone();
+        assert_eq!(red.size(), 5);
+        assert_eq!(
+            (red.min, red.max),
+            (GridPosition::new(0, 0), GridPosition::new(4, 0))
+        );
+        assert_eq!(
+            regions.region_at(GridPosition::new(1, 1)).unwrap().size(),
+            2
+        );
+
+        // Splitting the red row keeps the id for one part
+        grid.remove(GridPosition::new(2, 0));
+        regions.update(&grid, [GridPosition::new(2, 0)], same_kind);
+        assert_eq!(regions.len(), 3);
+        assert_eq!(regions.id_at(GridPosition::new(2, 0)), None);
+        let left = regions.id_at(GridPosition::new(0, 0)).unwrap();
+        let right = regions.id_at(GridPosition::new(4, 0)).unwrap();
+        assert_ne!(left, right);
+        assert!(left == red.id || right == red.id);
+
+        // Filling the gap merges them again
+        grid.insert(GridPosition::new(2, 0), RED, Rotation::Up);
+        regions.update(&grid, [GridPosition::new(2, 0)], same_kind);
+        assert_eq!(regions.len(), 2);
+        assert_eq!(
+            regions.region_at(GridPosition::new(4, 0)).unwrap().size(),
+            5
+        );
+        let merged = regions.id_at(GridPosition::new(0, 0)).unwrap();
+        assert!(merged == left || merged == right);
+    }
+
+    #[test]
+    fn test_tagged_regions() {
+        let water = GridTags::bit(0);
+        let mut grid = Grid::new();
+        for x in 0..4 {
+            grid.insert(GridPosition::new(x, 0), RED, Rotation::Up);
+            grid.get_mut(GridPosition::new(x, 0)).unwrap().tags = water;
+        }
+        grid.insert(GridPosition::new(1, 1), BLUE, Rotation::Up);
+
+        // Entities of any kind are in the same region while they match
+        let mut regions = GridRegions::label_tagged(&grid, Connectivity::Four, water);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions.id_at(GridPosition::new(1, 1)), None);
+
+        grid.get_mut(GridPosition::new(1, 1)).unwrap().tags = water;
+        grid.get_mut(GridPosition::new(2, 0)).unwrap().tags = GridTags::NONE;
+        let changed = [GridPosition::new(1, 1), GridPosition::new(2, 0)];
+        regions.update_tagged(&grid, changed, water);
+        assert_eq!(regions.len(), 2);
+        assert_eq!(
+            regions.region_at(GridPosition::new(1, 1)).unwrap().size(),
+            3
+        );
+        assert_eq!(regions.id_at(GridPosition::new(2, 0)), None);
+    }
+}
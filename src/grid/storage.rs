@@ -0,0 +1,110 @@
+use std::fmt;
+
+use bevy::{prelude::*, utils::HashMap};
+
+pub mod prelude {
+    pub use super::{GridOutOfBounds, GridStorage, HashMapStorage};
+}
+
+use crate::prelude::*;
+
+/// The backend storing the cells of a `Grid`
+pub trait GridStorage {
+    /// Get the lowest and highest cells the storage can hold, `None` if it is unbounded
+    fn bounds(&self) -> Option<(GridPosition, GridPosition)> {
+        None
+    }
+
+    /// Whether a cell can be stored
+    fn in_bounds(&self, position: GridPosition) -> bool {
+        self.bounds().is_none_or(|(min, max)| {
+            (min.x..=max.x).contains(&position.x) && (min.y..=max.y).contains(&position.y)
+        })
+    }
+
+    fn get(&self, position: GridPosition) -> Option<GridEntity>;
+
+    fn get_mut(&mut self, position: GridPosition) -> Option<&mut GridEntity>;
+
+    /// Insert an entry, returning the previous one
+    fn insert(
+        &mut self,
+        position: GridPosition,
+        entry: GridEntity,
+    ) -> Result<Option<GridEntity>, GridOutOfBounds>;
+
+    fn remove(&mut self, position: GridPosition) -> Option<GridEntity>;
+
+    /// The number of occupied cells
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the occupied cells
+    fn iter(&self) -> impl Iterator<Item = (GridPosition, &GridEntity)>;
+
+    /// Iterate mutably over the occupied cells
+    fn iter_mut(&mut self) -> impl Iterator<Item = (GridPosition, &mut GridEntity)>;
+
+    /// Get a lookup function for many nearby cells, which backends may speed up by caching
+    fn lookup(&self) -> impl FnMut(GridPosition) -> Option<GridEntity> {
+        |position| self.get(position)
+    }
+}
+
+/// Returned when inserting a cell outside of a bounded grid
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GridOutOfBounds(pub GridPosition);
+
+impl fmt::Display for GridOutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cell ({}, {}) is out of bounds", self.0.x, self.0.y)
+    }
+}
+
+impl std::error::Error for GridOutOfBounds {}
+
+/// Stores every cell in one flat hash map
+#[derive(Debug, Default, Clone, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub struct HashMapStorage {
+    pub data: HashMap<GridPosition, GridEntity>,
+}
+
+impl GridStorage for HashMapStorage {
+    fn get(&self, position: GridPosition) -> Option<GridEntity> {
+        self.data.get(&position).copied()
+    }
+
+    fn get_mut(&mut self, position: GridPosition) -> Option<&mut GridEntity> {
+        self.data.get_mut(&position)
+    }
+
+    fn insert(
+        &mut self,
+        position: GridPosition,
+        entry: GridEntity,
+    ) -> Result<Option<GridEntity>, GridOutOfBounds> {
+        Ok(self.data.insert(position, entry))
+    }
+
+    fn remove(&mut self, position: GridPosition) -> Option<GridEntity> {
+        self.data.remove(&position)
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (GridPosition, &GridEntity)> {
+        self.data.iter().map(|(position, entry)| (*position, entry))
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = (GridPosition, &mut GridEntity)> {
+        self.data
+            .iter_mut()
+            .map(|(position, entry)| (*position, entry))
+    }
+}
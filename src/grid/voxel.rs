@@ -0,0 +1,252 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+pub mod prelude {
+    pub use super::{VoxelGrid, VoxelNeighbor, VoxelPosition};
+}
+
+use crate::prelude::*;
+
+/// A cell of a three-dimensional grid, `z` being the level
+///
+/// Entities with this component are placed in the `VoxelGrid` of their grid state instead of
+/// a layer, and shouldn't also have a `GridPosition`.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Component, Reflect, Serialize, Deserialize,
+)]
+#[reflect(Component, Default)]
+pub struct VoxelPosition {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl VoxelPosition {
+    /// The offsets of the 6 cells sharing a face, horizontal first
+    pub const FACE_OFFSETS: [IVec3; 6] = [
+        IVec3::new(0, 1, 0),
+        IVec3::new(1, 0, 0),
+        IVec3::new(0, -1, 0),
+        IVec3::new(-1, 0, 0),
+        IVec3::new(0, 0, 1),
+        IVec3::new(0, 0, -1),
+    ];
+
+    pub fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Create a voxel position from a cell and a level
+    pub fn from_planar(position: GridPosition, level: i32) -> Self {
+        Self::new(position.x, position.y, level)
+    }
+
+    /// Get the cell of the position, without its level
+    pub fn planar(&self) -> GridPosition {
+        GridPosition::new(self.x, self.y)
+    }
+
+    /// Iterate over the 6 positions sharing a face
+    pub fn face_neighbors(&self) -> impl Iterator<Item = Self> + use<> {
+        let centre = *self;
+        Self::FACE_OFFSETS
+            .into_iter()
+            .map(move |offset| centre + offset)
+    }
+
+    /// Iterate over the 26 positions sharing a face, edge or corner, level by level
+    pub fn neighbors(&self) -> impl Iterator<Item = Self> + use<> {
+        let centre = *self;
+        (-1..=1)
+            .flat_map(|z| (-1..=1).flat_map(move |y| (-1..=1).map(move |x| IVec3::new(x, y, z))))
+            .filter(|offset| *offset != IVec3::ZERO)
+            .map(move |offset| centre + offset)
+    }
+}
+
+impl From<IVec3> for VoxelPosition {
+    fn from(vec: IVec3) -> Self {
+        Self::new(vec.x, vec.y, vec.z)
+    }
+}
+
+impl From<VoxelPosition> for IVec3 {
+    fn from(position: VoxelPosition) -> Self {
+        IVec3::new(position.x, position.y, position.z)
+    }
+}
+
+impl From<GridPosition> for VoxelPosition {
+    fn from(position: GridPosition) -> Self {
+        Self::from_planar(position, 0)
+    }
+}
+
+impl std::ops::Add<IVec3> for VoxelPosition {
+    type Output = Self;
+
+    fn add(self, rhs: IVec3) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+/// An occupied voxel next to another
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub struct VoxelNeighbor {
+    pub position: VoxelPosition,
+    pub entry: GridEntity,
+}
+
+/// The occupancy of a three-dimensional grid, stored as a `Grid` per level
+#[derive(Debug, Default, Clone, PartialEq, Eq, Reflect)]
+pub struct VoxelGrid<S = ChunkedStorage> {
+    levels: HashMap<i32, Grid<S>>,
+    len: usize,
+}
+
+impl VoxelGrid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S: GridStorage + Default> VoxelGrid<S> {
+    /// Get the grid of a level, `None` if nothing is placed on it
+    pub fn level(&self, level: i32) -> Option<&Grid<S>> {
+        self.levels.get(&level)
+    }
+
+    /// Iterate over the occupied levels and their grids, in no particular order
+    pub fn levels(&self) -> impl Iterator<Item = (i32, &Grid<S>)> {
+        self.levels.iter().map(|(level, grid)| (*level, grid))
+    }
+
+    /// Insert an entity, returning the previous occupant
+    pub fn insert(
+        &mut self,
+        position: VoxelPosition,
+        entity: Entity,
+        rotation: Rotation,
+    ) -> Result<Option<GridEntity>, GridOutOfBounds> {
+        let previous = self.levels.entry(position.z).or_default().try_insert(
+            position.planar(),
+            entity,
+            rotation,
+        )?;
+        if previous.is_none() {
+            self.len += 1;
+        }
+        Ok(previous)
+    }
+
+    pub fn remove(&mut self, position: VoxelPosition) -> Option<GridEntity> {
+        let level = self.levels.get_mut(&position.z)?;
+        let removed = level.remove(position.planar())?;
+        if level.is_empty() {
+            self.levels.remove(&position.z);
+        }
+        self.len -= 1;
+        Some(removed)
+    }
+
+    pub fn get(&self, position: VoxelPosition) -> Option<GridEntity> {
+        self.levels.get(&position.z)?.get(position.planar())
+    }
+
+    pub fn get_mut(&mut self, position: VoxelPosition) -> Option<&mut GridEntity> {
+        self.levels.get_mut(&position.z)?.get_mut(position.planar())
+    }
+
+    pub fn contains(&self, position: VoxelPosition) -> bool {
+        self.get(position).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (VoxelPosition, &GridEntity)> {
+        self.levels.iter().flat_map(|(level, grid)| {
+            grid.iter()
+                .map(|(position, entry)| (VoxelPosition::from_planar(position, *level), entry))
+        })
+    }
+
+    /// Get the highest occupied level of a column, `None` if the column is empty
+    pub fn top(&self, position: GridPosition) -> Option<i32> {
+        self.levels
+            .iter()
+            .filter(|(_, grid)| grid.contains(position))
+            .map(|(level, _)| *level)
+            .max()
+    }
+
+    /// Get the first free position at or above a position, to stack entities on top of each other
+    pub fn first_free_above(&self, position: VoxelPosition) -> VoxelPosition {
+        let mut position = position;
+        while self.contains(position) {
+            position.z += 1;
+        }
+        position
+    }
+
+    /// Get the occupied voxels sharing a face with a position, the 6-connected neighbors
+    pub fn get_face_neighbors(&self, position: VoxelPosition) -> Vec<VoxelNeighbor> {
+        self.occupied(position.face_neighbors())
+    }
+
+    /// Get the occupied voxels sharing a face, edge or corner with a position, the 26-connected neighbors
+    pub fn get_neighbors(&self, position: VoxelPosition) -> Vec<VoxelNeighbor> {
+        self.occupied(position.neighbors())
+    }
+
+    fn occupied(&self, positions: impl Iterator<Item = VoxelPosition>) -> Vec<VoxelNeighbor> {
+        positions
+            .filter_map(|position| {
+                self.get(position)
+                    .map(|entry| VoxelNeighbor { position, entry })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_voxel_position() {
+        let position = VoxelPosition::from(IVec3::new(1, -2, 3));
+        assert_eq!(position.planar(), GridPosition::new(1, -2));
+        assert_eq!(IVec3::from(position), IVec3::new(1, -2, 3));
+        assert_eq!(position.face_neighbors().count(), 6);
+        assert_eq!(position.neighbors().count(), 26);
+        assert!(!position.neighbors().any(|neighbor| neighbor == position));
+    }
+
+    #[test]
+    fn test_voxel_grid() {
+        let mut grid = VoxelGrid::new();
+        let entity = Entity::PLACEHOLDER;
+        let base = VoxelPosition::new(0, 0, 0);
+        grid.insert(base, entity, Rotation::Up).unwrap();
+        grid.insert(VoxelPosition::new(0, 0, 1), entity, Rotation::Up)
+            .unwrap();
+        grid.insert(VoxelPosition::new(1, 1, 1), entity, Rotation::Up)
+            .unwrap();
+        assert_eq!(grid.len(), 3);
+        assert_eq!(grid.top(GridPosition::new(0, 0)), Some(1));
+        assert_eq!(grid.first_free_above(base), VoxelPosition::new(0, 0, 2));
+
+        assert_eq!(grid.get_face_neighbors(base).len(), 1);
+        assert_eq!(grid.get_neighbors(base).len(), 2);
+
+        assert!(grid.remove(VoxelPosition::new(1, 1, 1)).is_some());
+        assert_eq!(grid.get_neighbors(base).len(), 1);
+        assert_eq!(grid.iter().count(), 2);
+    }
+}
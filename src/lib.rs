@@ -18,6 +18,7 @@ pub mod test {
             settings: EntityGridSettings {
                 cell_size: 1.0,
                 up_offset: 0.0,
+                ..default()
             },
         });
         app
@@ -0,0 +1,514 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use serde::{Deserialize, Serialize};
+
+pub mod prelude {
+    pub use super::{Autotile, AutotileMode, AutotileRegistry, AutotileRules, AutotileVariant};
+}
+
+use crate::prelude::*;
+
+/// Which neighbors make up the bitmask of an autotiled cell
+///
+/// Bit `n` of a mask is the neighbor in the `Direction8` of index `n`, clockwise from north.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum AutotileMode {
+    /// Wang 2-edge tiles, the 4 cardinal neighbors and 16 masks, for walls, roads or fences
+    #[default]
+    Edges,
+    /// Blob tiles, the 8 neighbors and 47 masks, a diagonal counting only if both cardinal
+    /// neighbors next to it connect
+    Blob,
+    /// Wang 2-corner tiles, 16 masks, a corner being set if the 3 neighbors around it connect
+    Corners,
+}
+
+impl AutotileMode {
+    /// Reduce the mask of the connected neighbors to the mask of the mode
+    pub fn reduce(&self, neighbors: u8) -> u8 {
+        let cardinal = neighbors & 0b0101_0101;
+        // The diagonals whose two cardinal neighbors are set
+        let corners = neighbors & (cardinal << 1) & cardinal.rotate_right(1) & 0b1010_1010;
+        match self {
+            Self::Edges => cardinal,
+            Self::Blob => cardinal | corners,
+            Self::Corners => corners,
+        }
+    }
+
+    /// Get the masks of the mode, in increasing order
+    pub fn masks(self) -> Vec<u8> {
+        let mut masks = (0..=u8::MAX)
+            .map(|neighbors| self.reduce(neighbors))
+            .collect::<Vec<_>>();
+        masks.sort();
+        masks.dedup();
+        masks
+    }
+}
+
+/// Rotate a mask clockwise
+fn rotate_mask(mask: u8, rotation: Rotation) -> u8 {
+    mask.rotate_left(rotation.quarter_turns() as u32 * 2)
+}
+
+/// The tile an autotiled entity shows, written by the autotile system
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct AutotileVariant {
+    /// The mask of the connected neighbors, reduced by the `AutotileMode`
+    pub mask: u8,
+    /// The index of the tile in the tileset
+    pub index: u32,
+    /// The rotation to show the tile with
+    pub rotation: Rotation,
+}
+
+/// The tiles picked for each mask of an `AutotileMode`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutotileRules {
+    pub mode: AutotileMode,
+    /// The neighbors connecting to the cell, by the tags of their cell
+    pub connects: TagFilter,
+    variants: HashMap<u8, (u32, Rotation)>,
+}
+
+impl AutotileRules {
+    /// Create rules for a tileset with one tile per shape, rotated to fit every mask
+    ///
+    /// Each shape is drawn for the lowest of its rotated masks, so connections favour the north.
+    /// Tiles are indexed in the order of these masks: edges run isolated, end, corner, straight,
+    /// tee and cross.
+    pub fn new(mode: AutotileMode, connects: impl Into<TagFilter>) -> Self {
+        let mut shapes = Vec::new();
+        let variants = mode
+            .masks()
+            .into_iter()
+            .map(|mask| {
+                let (shape, rotation) = [
+                    Rotation::Up,
+                    Rotation::Right,
+                    Rotation::Down,
+                    Rotation::Left,
+                ]
+                .into_iter()
+                .map(|rotation| (rotate_mask(mask, rotation.inverse()), rotation))
+                .min_by_key(|(shape, _)| *shape)
+                .unwrap();
+                let index = shapes
+                    .iter()
+                    .position(|known| *known == shape)
+                    .unwrap_or_else(|| {
+                        shapes.push(shape);
+                        shapes.len() - 1
+                    });
+                (mask, (index as u32, rotation))
+            })
+            .collect();
+        Self {
+            mode,
+            connects: connects.into(),
+            variants,
+        }
+    }
+
+    /// Create rules for a tileset with one tile per mask, unrotated
+    ///
+    /// Tiles are indexed in the order of their masks, from 16 tiles for edges and corners to 47
+    /// for blobs.
+    pub fn full(mode: AutotileMode, connects: impl Into<TagFilter>) -> Self {
+        Self {
+            mode,
+            connects: connects.into(),
+            variants: mode
+                .masks()
+                .into_iter()
+                .enumerate()
+                .map(|(index, mask)| (mask, (index as u32, Rotation::Up)))
+                .collect(),
+        }
+    }
+
+    /// Show a tile for a mask of the mode, replacing the tile of the rules
+    pub fn with_variant(mut self, mask: u8, index: u32, rotation: Rotation) -> Self {
+        self.variants.insert(mask, (index, rotation));
+        self
+    }
+
+    /// Get the tile of the connected neighbors, bit `n` being the neighbor in `Direction8` `n`
+    pub fn variant(&self, neighbors: u8) -> AutotileVariant {
+        let mask = self.mode.reduce(neighbors);
+        let (index, rotation) = self.variants.get(&mask).copied().unwrap_or_default();
+        AutotileVariant {
+            mask,
+            index,
+            rotation,
+        }
+    }
+
+    /// Get the tile of a cell of a grid
+    pub fn variant_at<S: GridStorage>(
+        &self,
+        grid: &Grid<S>,
+        position: GridPosition,
+    ) -> AutotileVariant {
+        let neighbors = Direction8::ALL
+            .iter()
+            .filter(|direction| {
+                grid.get(position.neighbor(**direction))
+                    .is_some_and(|entry| self.connects.matches(entry.tags))
+            })
+            .fold(0, |mask, direction| mask | 1 << direction.index());
+        self.variant(neighbors)
+    }
+}
+
+/// The autotile rules, keyed by the name `Autotile` components refer to
+#[derive(Debug, Default, Clone, Resource)]
+pub struct AutotileRegistry {
+    rules: HashMap<String, AutotileRules>,
+}
+
+impl AutotileRegistry {
+    /// Register rules, replacing any previous ones
+    pub fn register(&mut self, key: impl Into<String>, rules: AutotileRules) -> &mut Self {
+        self.rules.insert(key.into(), rules);
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&AutotileRules> {
+        self.rules.get(key)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.rules.contains_key(key)
+    }
+}
+
+/// Picks the `AutotileVariant` of a placed entity from its neighbors, with the rules
+/// registered under this key in the `AutotileRegistry`
+///
+/// The variant is recomputed when the entity or one of its neighbors is placed, moved, removed or
+/// changes tags. Multi-cell footprints are autotiled from their anchor.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Autotile(pub String);
+
+impl Autotile {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self(key.into())
+    }
+}
+
+/// The placed autotiled entities and their variant
+type AutotileQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        Ref<'static, Autotile>,
+        Option<&'static mut AutotileVariant>,
+    ),
+    With<GridPosition>,
+>;
+
+/// The grid, layer and cells last covered by each entity with a multi-cell footprint
+type FootprintCells = HashMap<Entity, (Option<Entity>, GridLayer, Vec<GridPosition>)>;
+
+/// The placements, footprint and tag changes that make cells dirty
+#[derive(SystemParam)]
+pub struct AutotileChanges<'w, 's> {
+    placed: EventReader<'w, 's, EntityPlaced>,
+    moved: EventReader<'w, 's, EntityMoved>,
+    removed: EventReader<'w, 's, EntityRemoved>,
+    rotated: EventReader<'w, 's, EntityRotated>,
+    tagged: Query<'w, 's, Entity, (With<GridPosition>, Changed<GridTags>)>,
+    untagged: RemovedComponents<'w, 's, GridTags>,
+    reshaped: Query<'w, 's, Entity, (With<GridPosition>, Changed<GridFootprint>)>,
+    unshaped: RemovedComponents<'w, 's, GridFootprint>,
+    footprints: Local<'s, FootprintCells>,
+}
+
+impl AutotileChanges<'_, '_> {
+    /// Get the cells whose occupant was placed, moved, rotated, removed or changed tags
+    ///
+    /// Every cell an entity with a multi-cell footprint covers, and covered before, is dirty.
+    fn dirty_cells(&mut self, grids: &Grids) -> HashSet<(Option<Entity>, GridLayer, GridPosition)> {
+        let mut dirty = HashSet::new();
+        let mut changed = HashSet::new();
+        for event in self.placed.read() {
+            dirty.insert((event.grid, event.layer, event.position));
+            changed.insert(event.entity);
+        }
+        for event in self.moved.read() {
+            dirty.insert((event.grid, event.layer, event.from));
+            dirty.insert((event.grid, event.layer, event.to));
+            changed.insert(event.entity);
+        }
+        for event in self.removed.read() {
+            dirty.insert((event.grid, event.layer, event.position));
+            changed.insert(event.entity);
+        }
+        changed.extend(self.rotated.read().map(|event| event.entity));
+        changed.extend(self.tagged.iter().chain(self.untagged.read()));
+        changed.extend(self.reshaped.iter().chain(self.unshaped.read()));
+
+        for entity in changed {
+            if let Some((grid, layer, cells)) = self.footprints.remove(&entity) {
+                dirty.extend(cells.into_iter().map(|cell| (grid, layer, cell)));
+            }
+            let Some(grid) = grids.find(entity) else {
+                continue;
+            };
+            let Some(state) = grids.state(grid) else {
+                continue;
+            };
+            let Some(&(layer, position)) = state.tracked.get(&entity) else {
+                continue;
+            };
+            match state.footprints.get(&entity) {
+                Some(cells) => {
+                    dirty.extend(cells.iter().map(|cell| (grid, layer, *cell)));
+                    self.footprints.insert(entity, (grid, layer, cells.clone()));
+                }
+                None => {
+                    dirty.insert((grid, layer, position));
+                }
+            }
+        }
+        dirty
+    }
+}
+
+/// Recompute the `AutotileVariant` of the cells that changed and of their neighbors
+///
+/// Every autotiled entity is recomputed when the `AutotileRegistry` changes.
+pub fn update_autotiles(
+    registry: Res<AutotileRegistry>,
+    mut changes: AutotileChanges,
+    mut tiles: AutotileQuery,
+    mut commands: Commands,
+    grids: Grids,
+) {
+    let mut dirty = changes.dirty_cells(&grids);
+    for (entity, autotile, _) in &tiles {
+        let changed = registry.is_changed() || autotile.is_changed();
+        if changed
+            && let Some(grid) = grids.find(entity)
+            && let Some(state) = grids.state(grid)
+            && let Some(&(layer, position)) = state.tracked.get(&entity)
+        {
+            dirty.insert((grid, layer, position));
+        }
+    }
+
+    let mut updated = HashSet::new();
+    for (grid, layer, position) in dirty {
+        let Some(state) = grids.state(grid) else {
+            continue;
+        };
+        let Some(grid_layer) = state.layer(layer) else {
+            continue;
+        };
+        let cells = std::iter::once(position).chain(
+            Direction8::ALL
+                .iter()
+                .map(|direction| position.neighbor(*direction)),
+        );
+        for cell in cells {
+            let Some(entry) = grid_layer.get(cell) else {
+                continue;
+            };
+            if !updated.insert(entry.entity) {
+                continue;
+            }
+            let Ok((_, autotile, current)) = tiles.get_mut(entry.entity) else {
+                continue;
+            };
+            let Some(rules) = registry.get(&autotile.0) else {
+                continue;
+            };
+            let anchor = state
+                .tracked
+                .get(&entry.entity)
+                .map_or(cell, |(_, anchor)| *anchor);
+            let variant = rules.variant_at(grid_layer, anchor);
+            match current {
+                Some(mut current) => {
+                    current.set_if_neq(variant);
+                }
+                None => {
+                    commands.entity(entry.entity).insert(variant);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALL: GridTags = GridTags::bit(0);
+
+    #[test]
+    fn test_autotile_rules() {
+        assert_eq!(AutotileMode::Edges.masks().len(), 16);
+        assert_eq!(AutotileMode::Blob.masks().len(), 47);
+        assert_eq!(AutotileMode::Corners.masks().len(), 16);
+        // A diagonal alone doesn't count
+        assert_eq!(AutotileMode::Blob.reduce(0b0000_0010), 0);
+        assert_eq!(AutotileMode::Blob.reduce(0b1100_0111), 0b1100_0111);
+        assert_eq!(AutotileMode::Corners.reduce(0b1100_0001), 0b1000_0000);
+
+        let rules = AutotileRules::new(AutotileMode::Edges, WALL);
+        let north = 1 << Direction8::North.index();
+        let east = 1 << Direction8::East.index();
+        let south = 1 << Direction8::South.index();
+        let west = 1 << Direction8::West.index();
+        let tile = |mask| {
+            let variant = rules.variant(mask);
+            (variant.index, variant.rotation)
+        };
+        assert_eq!(tile(0), (0, Rotation::Up));
+        assert_eq!(tile(north), (1, Rotation::Up));
+        assert_eq!(tile(west), (1, Rotation::Left));
+        assert_eq!(tile(east | south), (2, Rotation::Right));
+        assert_eq!(tile(east | west), (3, Rotation::Right));
+        assert_eq!(tile(north | east | west), (4, Rotation::Left));
+        assert_eq!(tile(north | east | south | west), (5, Rotation::Up));
+
+        let full =
+            AutotileRules::full(AutotileMode::Blob, WALL).with_variant(0, 99, Rotation::Down);
+        assert_eq!(full.variant(0xFF).index, 46);
+        assert_eq!(full.variant(0b0000_0010).index, 99);
+    }
+
+    #[test]
+    fn test_update_autotiles() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        app.world_mut()
+            .resource_mut::<AutotileRegistry>()
+            .register("wall", AutotileRules::new(AutotileMode::Edges, WALL));
+        let spawn_wall = |app: &mut App, x| {
+            app.world_mut()
+                .spawn((
+                    Transform::default(),
+                    GridPosition::new(x, 0),
+                    WALL,
+                    Autotile::new("wall"),
+                ))
+                .id()
+        };
+        let left = spawn_wall(&mut app, 0);
+        let middle = spawn_wall(&mut app, 1);
+        app.update();
+        let variant = |app: &App, entity| *app.world().get::<AutotileVariant>(entity).unwrap();
+        assert_eq!(variant(&app, left).index, 1);
+        assert_eq!(variant(&app, left).rotation, Rotation::Right);
+
+        // Placing a neighbor updates the others
+        let right = spawn_wall(&mut app, 2);
+        app.update();
+        assert_eq!(variant(&app, middle).index, 3);
+        assert_eq!(variant(&app, right).rotation, Rotation::Left);
+
+        // So does removing one, or changing its tags
+        app.world_mut().despawn(middle);
+        app.update();
+        assert_eq!(variant(&app, left).index, 0);
+        app.world_mut().entity_mut(right).insert(GridTags::NONE);
+        app.world_mut()
+            .entity_mut(left)
+            .insert(GridPosition::new(1, 0));
+        app.update();
+        assert_eq!(variant(&app, right).index, 1);
+        assert_eq!(variant(&app, left).index, 0);
+
+        // Neighbors that aren't autotiled count through their tags too
+        let post = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(1, 1), WALL))
+            .id();
+        app.update();
+        assert_eq!(variant(&app, left).index, 1);
+        app.world_mut().entity_mut(post).insert(GridTags::NONE);
+        app.update();
+        assert_eq!(variant(&app, left).index, 0);
+        app.world_mut().entity_mut(post).insert(WALL);
+        app.update();
+        assert_eq!(variant(&app, left).index, 1);
+        app.world_mut().entity_mut(post).remove::<GridTags>();
+        app.update();
+        assert_eq!(variant(&app, left).index, 0);
+    }
+
+    #[test]
+    fn test_autotile_footprints() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        app.world_mut()
+            .resource_mut::<AutotileRegistry>()
+            .register("wall", AutotileRules::new(AutotileMode::Edges, WALL));
+        let wall = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GridPosition::new(2, 0),
+                WALL,
+                Autotile::new("wall"),
+            ))
+            .id();
+        app.update();
+        let variant = |app: &App| app.world().get::<AutotileVariant>(wall).unwrap().index;
+        assert_eq!(variant(&app), 0);
+
+        // The wall only neighbors the second cell of the gate
+        let gate = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GridPosition::new(0, 0),
+                GridFootprint::rectangle(2, 1),
+                WALL,
+            ))
+            .id();
+        app.update();
+        assert_eq!(variant(&app), 1);
+
+        // Turning the gate away frees the cell next to the wall
+        app.world_mut()
+            .entity_mut(gate)
+            .insert(GridRotation(Rotation::Left));
+        app.update();
+        assert_eq!(variant(&app), 0);
+        app.world_mut()
+            .entity_mut(gate)
+            .insert(GridRotation(Rotation::Up));
+        app.update();
+        assert_eq!(variant(&app), 1);
+
+        // So does moving, reshaping or removing it
+        *app.world_mut().get_mut::<GridPosition>(gate).unwrap() = GridPosition::new(-1, 0);
+        app.update();
+        assert_eq!(variant(&app), 0);
+        *app.world_mut().get_mut::<GridPosition>(gate).unwrap() = GridPosition::new(0, 0);
+        app.update();
+        assert_eq!(variant(&app), 1);
+        app.world_mut().entity_mut(gate).remove::<GridFootprint>();
+        app.update();
+        assert_eq!(variant(&app), 0);
+        app.world_mut()
+            .entity_mut(gate)
+            .insert(GridFootprint::rectangle(2, 1));
+        app.update();
+        assert_eq!(variant(&app), 1);
+        app.world_mut().despawn(gate);
+        app.update();
+        assert_eq!(variant(&app), 0);
+    }
+}
@@ -0,0 +1,52 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+pub mod prelude {
+    pub use super::GridCursor;
+}
+
+use crate::prelude::*;
+
+/// The cell under the cursor of the primary window
+///
+/// Updated every frame by casting the cursor through the first active camera onto the grid plane,
+/// which works for both 2D and 3D cameras.
+#[derive(Debug, Default, Clone, PartialEq, Resource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct GridCursor {
+    /// The grid to pick, `None` for the default grid
+    pub grid: Option<Entity>,
+    /// The layer to pick the entry from
+    pub layer: GridLayer,
+    /// The hovered cell, `None` if the cursor is outside the window or misses the grid
+    pub position: Option<GridPosition>,
+    /// The entity occupying the hovered cell
+    pub entry: Option<GridEntity>,
+}
+
+/// Resolve the cursor of the primary window to a cell of the picked grid
+pub fn update_grid_cursor(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    grids: Grids,
+    mut cursor: ResMut<GridCursor>,
+) {
+    let position = windows
+        .get_single()
+        .ok()
+        .and_then(Window::cursor_position)
+        .and_then(|screen| {
+            cameras
+                .iter()
+                .filter(|(camera, _)| camera.is_active)
+                .find_map(|(camera, transform)| camera.viewport_to_world(transform, screen).ok())
+        })
+        .and_then(|ray| grids.ray_to_cell(cursor.grid, ray));
+    let entry = position.and_then(|position| grids.state(cursor.grid)?.get(cursor.layer, position));
+
+    cursor.set_if_neq(GridCursor {
+        grid: cursor.grid,
+        layer: cursor.layer,
+        position,
+        entry,
+    });
+}
@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+
+pub mod prelude {
+    pub use super::{
+        ChunkLoaded, ChunkUnloaded, EntityMoved, EntityPlaced, EntityRemoved, EntityRotated,
+        MotionFinished, OccupancyConflict,
+    };
+}
+
+use crate::prelude::*;
+
+/// Sent whenever an entity is placed on a cell that is already occupied
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct OccupancyConflict {
+    /// The grid entity, `None` for the default grid
+    pub grid: Option<Entity>,
+    /// The layer of the grid
+    pub layer: GridLayer,
+    /// The contested cell
+    pub position: GridPosition,
+    /// The entity that was occupying the cell
+    pub occupant: Entity,
+    /// The entity that tried to enter the cell
+    pub incoming: Entity,
+    /// The policy that resolved the conflict
+    pub policy: OccupancyPolicy,
+}
+
+/// Sent when an entity is inserted in the grid
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct EntityPlaced {
+    /// The grid entity, `None` for the default grid
+    pub grid: Option<Entity>,
+    /// The layer of the grid
+    pub layer: GridLayer,
+    /// The placed entity
+    pub entity: Entity,
+    /// The cell the entity was placed in
+    pub position: GridPosition,
+    /// The rotation of the entity
+    pub rotation: Rotation,
+}
+
+/// Sent when an entity moves from one cell to another
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct EntityMoved {
+    /// The grid entity, `None` for the default grid
+    pub grid: Option<Entity>,
+    /// The layer of the grid
+    pub layer: GridLayer,
+    /// The moved entity
+    pub entity: Entity,
+    /// The cell the entity left
+    pub from: GridPosition,
+    /// The cell the entity entered
+    pub to: GridPosition,
+    /// The rotation of the entity
+    pub rotation: Rotation,
+}
+
+/// Sent when an entity is removed from the grid, either by losing its position or being despawned
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct EntityRemoved {
+    /// The grid entity, `None` for the default grid
+    pub grid: Option<Entity>,
+    /// The layer of the grid
+    pub layer: GridLayer,
+    /// The removed entity
+    pub entity: Entity,
+    /// The cell the entity was removed from
+    pub position: GridPosition,
+    /// The rotation the entity had
+    pub rotation: Rotation,
+}
+
+/// Sent when the rotation of an entity in the grid changes
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct EntityRotated {
+    /// The grid entity, `None` for the default grid
+    pub grid: Option<Entity>,
+    /// The layer of the grid
+    pub layer: GridLayer,
+    /// The rotated entity
+    pub entity: Entity,
+    /// The cell of the entity
+    pub position: GridPosition,
+    /// The previous rotation
+    pub from: Rotation,
+    /// The new rotation
+    pub to: Rotation,
+}
+
+/// Sent when a chunk streams in around a `GridStreamAnchor`
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct ChunkLoaded {
+    /// The grid entity, `None` for the default grid
+    pub grid: Option<Entity>,
+    /// The loaded chunk
+    pub chunk: ChunkPosition,
+    /// The entities respawned from the `GridChunkStore`, spawned once commands are applied
+    pub respawned: usize,
+}
+
+/// Sent when a chunk streams out, after its entities were stored and despawned
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct ChunkUnloaded {
+    /// The grid entity, `None` for the default grid
+    pub grid: Option<Entity>,
+    /// The unloaded chunk
+    pub chunk: ChunkPosition,
+    /// The entities written to the `GridChunkStore`
+    pub stored: usize,
+}
+
+/// Sent when an entity with a `GridMotion` reaches its cell
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct MotionFinished {
+    /// The entity that stopped moving
+    pub entity: Entity,
+    /// The cell it reached
+    pub position: GridPosition,
+    /// The waypoints left to visit
+    pub remaining: usize,
+}
@@ -0,0 +1,472 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+pub mod prelude {
+    pub use super::{BlocksSight, GridFog, GridTeam, GridViewer, HideInFog, LocalTeam, TeamFog};
+}
+
+use crate::prelude::*;
+
+/// Sees the cells around its `GridPosition`, revealing them to its `GridTeam`
+///
+/// The field of view is recomputed whenever the viewer moves or an entity that `BlocksSight`
+/// changes, and stored in the `GridFog` of its grid.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct GridViewer {
+    /// The distance in cells the viewer sees at
+    pub range: u32,
+}
+
+impl Default for GridViewer {
+    fn default() -> Self {
+        Self { range: 8 }
+    }
+}
+
+/// The team an entity belongs to, entities without this component are in team 0
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct GridTeam(pub u32);
+
+/// Marks placed entities hiding the cells behind them from `GridViewer`s
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct BlocksSight;
+
+/// Marks placed entities hidden while none of their cells is visible to the `LocalTeam`
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct HideInFog;
+
+/// The team of the local player, whose fog hides the `HideInFog` entities
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Resource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct LocalTeam(pub u32);
+
+/// What a team sees of a grid
+#[derive(Debug, Default, Clone, PartialEq, Eq, Reflect)]
+pub struct TeamFog {
+    /// The cells seen by a viewer of the team right now
+    pub visible: CellBitmap,
+    /// The cells ever seen by a viewer of the team
+    pub explored: CellBitmap,
+}
+
+/// The fog of war of a grid, per team
+#[derive(Debug, Default, Clone, PartialEq, Eq, Reflect)]
+pub struct GridFog {
+    /// The team and visible cells of each viewer
+    pub viewers: HashMap<Entity, (u32, CellBitmap)>,
+    /// The fog of each team with a viewer
+    pub teams: HashMap<u32, TeamFog>,
+}
+
+impl GridFog {
+    /// Get the fog of a team, `None` if the team never had a viewer in the grid
+    pub fn team(&self, team: u32) -> Option<&TeamFog> {
+        self.teams.get(&team)
+    }
+
+    /// Whether a team sees a cell right now
+    pub fn is_visible(&self, team: u32, position: GridPosition) -> bool {
+        self.team(team)
+            .is_some_and(|fog| fog.visible.contains(position))
+    }
+
+    /// Whether a team ever saw a cell
+    pub fn is_explored(&self, team: u32, position: GridPosition) -> bool {
+        self.team(team)
+            .is_some_and(|fog| fog.explored.contains(position))
+    }
+
+    /// Set the cells a viewer sees, call `refresh` to update the teams
+    pub fn set_viewer(&mut self, viewer: Entity, team: u32, visible: CellBitmap) {
+        self.viewers.insert(viewer, (team, visible));
+    }
+
+    /// Forget a viewer, call `refresh` to update the teams
+    pub fn remove_viewer(&mut self, viewer: Entity) -> Option<(u32, CellBitmap)> {
+        self.viewers.remove(&viewer)
+    }
+
+    /// Rebuild the visible cells of every team from its viewers, exploring them
+    pub fn refresh(&mut self) {
+        for fog in self.teams.values_mut() {
+            fog.visible.clear();
+        }
+        for (team, visible) in self.viewers.values() {
+            let fog = self.teams.entry(*team).or_default();
+            fog.visible.union_with(visible);
+            fog.explored.union_with(visible);
+        }
+    }
+}
+
+impl EntityGridState {
+    /// Get the cells visible from a position within a range
+    ///
+    /// A cell is opaque if its occupant on any layer is accepted by `blocks`.
+    pub fn field_of_view(
+        &self,
+        origin: GridPosition,
+        range: u32,
+        blocks: impl Fn(&GridEntity) -> bool,
+    ) -> CellBitmap {
+        shadowcast(origin, range, |position| {
+            self.layers()
+                .any(|(_, grid)| grid.get(position).is_some_and(|entry| blocks(&entry)))
+        })
+    }
+}
+
+/// The placed viewers
+type ViewerQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        Ref<'static, GridViewer>,
+        Ref<'static, GridPosition>,
+        Option<Ref<'static, InGrid>>,
+        Option<Ref<'static, GridTeam>>,
+    ),
+>;
+
+/// The sight blockers that were added, moved or changed the cells they cover
+type ChangedBlockerQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Ref<'static, BlocksSight>,
+        Ref<'static, GridPosition>,
+        Option<Ref<'static, InGrid>>,
+        Option<Ref<'static, GridLayer>>,
+        Option<Ref<'static, GridRotation>>,
+        Option<Ref<'static, GridFootprint>>,
+    ),
+    (
+        With<BlocksSight>,
+        Or<(
+            Added<BlocksSight>,
+            Changed<GridPosition>,
+            Changed<InGrid>,
+            Changed<GridLayer>,
+            Changed<GridRotation>,
+            Changed<GridFootprint>,
+        )>,
+    ),
+>;
+
+/// The changes to the sight blockers of the grids
+#[derive(SystemParam)]
+pub struct BlockerChanges<'w, 's> {
+    changed: ChangedBlockerQuery<'w, 's>,
+    blockers: Query<'w, 's, (), With<BlocksSight>>,
+    removed_blockers: RemovedComponents<'w, 's, BlocksSight>,
+    removed: EventReader<'w, 's, EntityRemoved>,
+}
+
+impl BlockerChanges<'_, '_> {
+    /// Get the grids whose sight blockers were added, moved or removed
+    fn changed_grids(&mut self, grids: &Grids) -> HashSet<Option<Entity>> {
+        let mut changed = HashSet::new();
+        for (blocks, position, in_grid, layer, rotation, footprint) in &self.changed {
+            // Rotating only changes the cells of a multi-cell footprint
+            let rotated = rotation.is_some_and(|rotation| rotation.is_changed())
+                && footprint
+                    .as_ref()
+                    .is_some_and(|footprint| footprint.is_multi_cell());
+            if blocks.is_added()
+                || position.is_changed()
+                || rotated
+                || in_grid.as_ref().is_some_and(|in_grid| in_grid.is_changed())
+                || layer.is_some_and(|layer| layer.is_changed())
+                || footprint.is_some_and(|footprint| footprint.is_changed())
+            {
+                changed.insert(in_grid.map(|in_grid| in_grid.0));
+            }
+        }
+
+        // Blockers that lost the component but are still placed
+        let removed_blockers: HashSet<Entity> = self.removed_blockers.read().collect();
+        for entity in &removed_blockers {
+            if let Some(grid) = grids.find(*entity) {
+                changed.insert(grid);
+            }
+        }
+        // Blockers that left a grid, were despawned or lost their position
+        for event in self.removed.read() {
+            if removed_blockers.contains(&event.entity) || self.blockers.contains(event.entity) {
+                changed.insert(event.grid);
+            }
+        }
+        changed
+    }
+}
+
+/// Recompute the field of view of the viewers that moved and update the fog of their grid
+///
+/// The viewers of a grid are all recomputed when an entity that `BlocksSight` is placed, moved,
+/// rotated or removed in it.
+pub fn update_grid_viewers(
+    viewers: ViewerQuery,
+    mut blocker_changes: BlockerChanges,
+    mut removed_viewers: RemovedComponents<GridViewer>,
+    mut removed_positions: RemovedComponents<GridPosition>,
+    mut grids: Grids,
+) {
+    let blocked_grids = blocker_changes.changed_grids(&grids);
+    let mut refreshed = HashSet::new();
+
+    for entity in removed_viewers.read().chain(removed_positions.read()) {
+        if let Some(grid) = grids.find_viewer(entity)
+            && let Some(state) = grids.state_mut(grid)
+        {
+            state.fog.remove_viewer(entity);
+            refreshed.insert(grid);
+        }
+    }
+
+    for (entity, viewer, position, in_grid, team) in &viewers {
+        let changed = blocked_grids.contains(&in_grid.as_ref().map(|in_grid| in_grid.0))
+            || viewer.is_changed()
+            || position.is_changed()
+            || in_grid.as_ref().is_some_and(|in_grid| in_grid.is_changed())
+            || team.as_ref().is_some_and(|team| team.is_changed());
+        if !changed {
+            continue;
+        }
+        let grid = in_grid.map(|in_grid| in_grid.0);
+        let team = team.map_or(0, |team| team.0);
+
+        // Leave the fog of the previous grid
+        if let Some(previous_grid) = grids.find_viewer(entity)
+            && previous_grid != grid
+            && let Some(previous_state) = grids.state_mut(previous_grid)
+        {
+            previous_state.fog.remove_viewer(entity);
+            refreshed.insert(previous_grid);
+        }
+
+        let Some(state) = grids.state_mut(grid) else {
+            continue;
+        };
+        let visible = state.field_of_view(*position, viewer.range, |entry| {
+            blocker_changes.blockers.contains(entry.entity)
+        });
+        state.fog.set_viewer(entity, team, visible);
+        refreshed.insert(grid);
+    }
+
+    for grid in refreshed {
+        if let Some(state) = grids.state_mut(grid) {
+            state.fog.refresh();
+        }
+    }
+}
+
+/// The entities hidden by the fog of the local team
+type FoggedQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GridPosition,
+        Option<&'static InGrid>,
+        &'static mut Visibility,
+    ),
+    With<HideInFog>,
+>;
+
+/// Hide the `HideInFog` entities none of whose cells is visible to the `LocalTeam`
+pub fn apply_fog_visibility(local: Res<LocalTeam>, mut fogged: FoggedQuery, grids: Grids) {
+    for (entity, position, in_grid, mut visibility) in &mut fogged {
+        let Some(state) = grids.state(in_grid.map(|InGrid(grid)| *grid)) else {
+            continue;
+        };
+        let seen = match state.footprints.get(&entity) {
+            Some(cells) => cells
+                .iter()
+                .any(|cell| state.fog.is_visible(local.0, *cell)),
+            None => state.fog.is_visible(local.0, *position),
+        };
+        visibility.set_if_neq(if seen {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fog_of_war() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        app.insert_resource(LocalTeam(1));
+
+        for y in -2..=2 {
+            app.world_mut()
+                .spawn((Transform::default(), GridPosition::new(2, y), BlocksSight));
+        }
+        let viewer = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GridPosition::new(0, 0),
+                GridViewer { range: 4 },
+                GridTeam(1),
+            ))
+            .id();
+        let hidden = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                Visibility::default(),
+                GridPosition::new(4, 0),
+                HideInFog,
+            ))
+            .id();
+        let shown = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                Visibility::default(),
+                GridPosition::new(-3, 0),
+                HideInFog,
+            ))
+            .id();
+        app.update();
+
+        let fog = &app.world().resource::<EntityGridState>().fog;
+        assert!(fog.is_visible(1, GridPosition::new(-4, 0)));
+        assert!(!fog.is_visible(1, GridPosition::new(3, 0)));
+        assert!(!fog.is_visible(0, GridPosition::new(0, 0)));
+        assert_eq!(
+            app.world().get::<Visibility>(hidden),
+            Some(&Visibility::Hidden)
+        );
+        assert_eq!(
+            app.world().get::<Visibility>(shown),
+            Some(&Visibility::Inherited)
+        );
+
+        // Moving away keeps the cells explored
+        *app.world_mut().get_mut::<GridPosition>(viewer).unwrap() = GridPosition::new(0, 8);
+        app.update();
+        let fog = &app.world().resource::<EntityGridState>().fog;
+        assert!(!fog.is_visible(1, GridPosition::new(-3, 0)));
+        assert!(fog.is_explored(1, GridPosition::new(-3, 0)));
+        assert!(fog.is_visible(1, GridPosition::new(0, 12)));
+        assert_eq!(
+            app.world().get::<Visibility>(shown),
+            Some(&Visibility::Hidden)
+        );
+
+        // Without viewers nothing is visible
+        app.world_mut().despawn(viewer);
+        app.update();
+        let fog = &app.world().resource::<EntityGridState>().fog;
+        assert!(fog.team(1).is_some_and(|fog| fog.visible.is_empty()));
+        assert!(fog.viewers.is_empty());
+    }
+
+    #[test]
+    fn test_blocker_changes() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        let other = app
+            .world_mut()
+            .spawn(EntityGridState::new(EntityGridSettings::default()))
+            .id();
+        let viewer = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(0, 0), GridViewer {
+                range: 4,
+            }))
+            .id();
+        let blocker = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GridPosition::new(2, 0),
+                GridFootprint::rectangle(2, 1),
+                BlocksSight,
+            ))
+            .id();
+        app.update();
+
+        // The field of view of the viewer matches the current cells of the blocker
+        let seen = |app: &App| {
+            let state = app.world().resource::<EntityGridState>();
+            let expected =
+                state.field_of_view(GridPosition::new(0, 0), 4, |entry| entry.entity == blocker);
+            state.fog.viewers[&viewer].1 == expected
+        };
+        let visible = |app: &App| {
+            app.world().resource::<EntityGridState>().fog.viewers[&viewer]
+                .1
+                .clone()
+        };
+        assert!(seen(&app));
+        let before = visible(&app);
+
+        app.world_mut()
+            .entity_mut(blocker)
+            .insert(GridRotation(Rotation::Left));
+        app.update();
+        assert!(seen(&app));
+        assert_ne!(visible(&app), before);
+
+        app.world_mut()
+            .entity_mut(blocker)
+            .insert(GridFootprint::rectangle(3, 1));
+        app.update();
+        assert!(seen(&app));
+
+        app.world_mut().entity_mut(blocker).remove::<GridPosition>();
+        app.update();
+        assert!(seen(&app));
+        assert!(
+            app.world()
+                .resource::<EntityGridState>()
+                .fog
+                .is_visible(0, GridPosition::new(3, 0))
+        );
+
+        app.world_mut()
+            .entity_mut(blocker)
+            .insert(GridPosition::new(2, 0));
+        app.update();
+        assert!(seen(&app));
+        assert_ne!(visible(&app), before);
+
+        app.world_mut().entity_mut(blocker).insert(InGrid(other));
+        app.update();
+        assert!(seen(&app));
+        assert_eq!(
+            visible(&app),
+            app.world().resource::<EntityGridState>().field_of_view(
+                GridPosition::new(0, 0),
+                4,
+                |_| false
+            )
+        );
+
+        // Blockers of another grid leave the viewers of the default grid alone
+        app.world_mut()
+            .resource_mut::<EntityGridState>()
+            .fog
+            .set_viewer(viewer, 0, CellBitmap::default());
+        *app.world_mut().get_mut::<GridPosition>(blocker).unwrap() = GridPosition::new(1, 0);
+        app.update();
+        assert!(visible(&app).is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+use bevy::{
+    ecs::{
+        entity::{EntityMapper, MapEntities},
+        reflect::ReflectMapEntities,
+        system::SystemParam,
+    },
+    prelude::*,
+};
+
+pub mod prelude {
+    pub use super::{Grids, InGrid};
+}
+
+use crate::prelude::*;
+
+/// Places a `GridPosition` in the grid of another entity instead of the default grid
+///
+/// The grid entity holds its own `EntityGridState` component, and its `Transform` is used as the
+/// origin of the grid. Entities in the grid should not be children of the grid entity, their
+/// `Transform` is already written relative to the origin.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Component, Reflect)]
+#[reflect(Component, MapEntities)]
+pub struct InGrid(pub Entity);
+
+impl MapEntities for InGrid {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.0 = entity_mapper.map_entity(self.0);
+    }
+}
+
+/// The grid entities, with their state and origin
+type GridQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut EntityGridState,
+        Option<&'static Transform>,
+    ),
+    (
+        Without<GridPosition>,
+        Without<VoxelPosition>,
+        Without<GridPreview>,
+    ),
+>;
+
+/// Access to every grid, keyed by grid entity
+///
+/// `None` is the default grid stored in the `EntityGridState` resource.
+#[derive(SystemParam)]
+pub struct Grids<'w, 's> {
+    default: ResMut<'w, EntityGridState>,
+    grids: GridQuery<'w, 's>,
+    rules: Res<'w, GridPlacementRules>,
+}
+
+impl Grids<'_, '_> {
+    /// Get the state of a grid
+    pub fn state(&self, grid: Option<Entity>) -> Option<&EntityGridState> {
+        match grid {
+            Some(grid) => self.grids.get(grid).ok().map(|(_, state, _)| state),
+            None => Some(&self.default),
+        }
+    }
+
+    /// Get the mutable state of a grid
+    pub fn state_mut(&mut self, grid: Option<Entity>) -> Option<&mut EntityGridState> {
+        match grid {
+            Some(grid) => self
+                .grids
+                .get_mut(grid)
+                .ok()
+                .map(|(_, state, _)| state.into_inner()),
+            None => Some(self.default.as_mut()),
+        }
+    }
+
+    /// Get the origin of a grid
+    pub fn origin(&self, grid: Option<Entity>) -> Transform {
+        grid.and_then(|grid| self.grids.get(grid).ok())
+            .and_then(|(_, _, origin)| origin.copied())
+            .unwrap_or_default()
+    }
+
+    /// Get the registered `GridPlacementRules`
+    pub fn placement_rules(&self) -> &GridPlacementRules {
+        &self.rules
+    }
+
+    /// Check a placement in a grid against the registered `GridPlacementRules`
+    pub fn can_place(
+        &self,
+        grid: Option<Entity>,
+        request: &PlacementRequest,
+    ) -> Result<(), PlacementDenied> {
+        let state = match grid {
+            Some(entity) => self
+                .state(grid)
+                .ok_or(PlacementDenied::NoGridState(entity))?,
+            None => &self.default,
+        };
+        self.rules.check(state, request)
+    }
+
+    /// Get the world position of a cell in a grid
+    pub fn cell_to_world(
+        &self,
+        grid: Option<Entity>,
+        position: GridPosition,
+        anchor: CellAnchor,
+    ) -> Option<Vec3> {
+        let local = self.state(grid)?.settings.cell_to_world(position, anchor);
+        Some(self.origin(grid).transform_point(local))
+    }
+
+    /// Get the cell of a grid containing a world position
+    pub fn world_to_cell(&self, grid: Option<Entity>, world: Vec3) -> Option<GridPosition> {
+        let local = self
+            .origin(grid)
+            .compute_affine()
+            .inverse()
+            .transform_point3(world);
+        Some(self.state(grid)?.settings.world_to_cell(local))
+    }
+
+    /// Get the cell of a grid a world ray hits
+    pub fn ray_to_cell(&self, grid: Option<Entity>, ray: Ray3d) -> Option<GridPosition> {
+        let inverse = self.origin(grid).compute_affine().inverse();
+        let local = Ray3d {
+            origin: inverse.transform_point3(ray.origin),
+            direction: Dir3::new(inverse.transform_vector3(*ray.direction)).ok()?,
+        };
+        self.state(grid)?.settings.ray_to_cell(local)
+    }
+
+    /// Find the grid an entity with a `VoxelPosition` is currently registered in
+    pub fn find_voxel(&self, entity: Entity) -> Option<Option<Entity>> {
+        if self.default.voxel_tracked.contains_key(&entity) {
+            return Some(None);
+        }
+        self.grids
+            .iter()
+            .find(|(_, state, _)| state.voxel_tracked.contains_key(&entity))
+            .map(|(grid, ..)| Some(grid))
+    }
+
+    /// Find the grid whose fog holds the field of view of a `GridViewer`
+    pub fn find_viewer(&self, viewer: Entity) -> Option<Option<Entity>> {
+        if self.default.fog.viewers.contains_key(&viewer) {
+            return Some(None);
+        }
+        self.grids
+            .iter()
+            .find(|(_, state, _)| state.fog.viewers.contains_key(&viewer))
+            .map(|(grid, ..)| Some(grid))
+    }
+
+    /// Find the grid an entity is currently registered in
+    pub fn find(&self, entity: Entity) -> Option<Option<Entity>> {
+        if self.default.tracked.contains_key(&entity) {
+            return Some(None);
+        }
+        self.grids
+            .iter()
+            .find(|(_, state, _)| state.tracked.contains_key(&entity))
+            .map(|(grid, ..)| Some(grid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn test_grid_origin_conversion() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        let grid = app
+            .world_mut()
+            .spawn((
+                EntityGridState::new(EntityGridSettings::default()),
+                Transform::from_xyz(10.0, 2.0, 0.0),
+            ))
+            .id();
+
+        app.world_mut()
+            .run_system_once(move |grids: Grids| {
+                assert_eq!(
+                    grids.cell_to_world(Some(grid), GridPosition::new(1, 1), CellAnchor::Centre),
+                    Some(Vec3::new(11.0, 2.0, 1.0))
+                );
+                assert_eq!(
+                    grids.world_to_cell(Some(grid), Vec3::new(11.2, 0.0, 0.8)),
+                    Some(GridPosition::new(1, 1))
+                );
+                assert_eq!(
+                    grids.world_to_cell(None, Vec3::new(11.2, 0.0, 0.8)),
+                    Some(GridPosition::new(11, 1))
+                );
+                let ray = Ray3d::new(Vec3::new(8.0, 10.0, 3.0), Dir3::NEG_Y);
+                assert_eq!(
+                    grids.ray_to_cell(Some(grid), ray),
+                    Some(GridPosition::new(-2, 3))
+                );
+            })
+            .unwrap();
+    }
+}
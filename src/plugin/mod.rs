@@ -1,12 +1,34 @@
+pub mod autotile;
+pub mod cursor;
+pub mod events;
+pub mod fog;
+pub mod grids;
+pub mod motion;
+pub mod placement;
+pub mod remote;
+pub mod save;
 pub mod settings;
 pub mod state;
+pub mod streaming;
+pub mod systems;
+pub mod voxels;
 
 use bevy::prelude::*;
 
 pub mod prelude {
     pub use super::EntityGridPlugin;
+    pub use super::autotile::prelude::*;
+    pub use super::cursor::prelude::*;
+    pub use super::events::prelude::*;
+    pub use super::fog::prelude::*;
+    pub use super::grids::prelude::*;
+    pub use super::motion::prelude::*;
+    pub use super::placement::prelude::*;
+    pub use super::remote::prelude::*;
+    pub use super::save::prelude::*;
     pub use super::settings::prelude::*;
     pub use super::state::prelude::*;
+    pub use super::streaming::prelude::*;
 }
 
 use crate::prelude::*;
@@ -21,57 +43,72 @@ pub struct EntityGridPlugin {
 /// Plugin implementation
 impl Plugin for EntityGridPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(EntityGridState {
-            settings: self.settings.clone(),
-            grid: Grid::default(),
-            spawn_rotation: Rotation::default(),
-        });
+        app.insert_resource(EntityGridState::new(self.settings.clone()));
+
+        app.add_event::<OccupancyConflict>()
+            .add_event::<EntityPlaced>()
+            .add_event::<EntityMoved>()
+            .add_event::<EntityRemoved>()
+            .add_event::<EntityRotated>()
+            .add_event::<MotionFinished>()
+            .add_event::<ChunkLoaded>()
+            .add_event::<ChunkUnloaded>();
+
+        app.init_resource::<GridCursor>()
+            .init_resource::<GridSpawnRegistry>()
+            .init_resource::<GridChunkStore>()
+            .init_resource::<GridPlacementRules>()
+            .init_resource::<LocalTeam>()
+            .init_resource::<AutotileRegistry>();
+
+        app.register_type::<GridPosition>()
+            .register_type::<GridRotation>()
+            .register_type::<GridLayer>()
+            .register_type::<InGrid>()
+            .register_type::<GridPrefab>()
+            .register_type::<Rotation>()
+            .register_type::<HexFacing>()
+            .register_type::<GridFootprint>()
+            .register_type::<GridMotion>()
+            .register_type::<VoxelPosition>()
+            .register_type::<GridViewer>()
+            .register_type::<GridTeam>()
+            .register_type::<BlocksSight>()
+            .register_type::<HideInFog>()
+            .register_type::<LocalTeam>()
+            .register_type::<GridTags>()
+            .register_type::<Autotile>()
+            .register_type::<AutotileVariant>()
+            .register_type::<GridEntity>()
+            .register_type::<Grid>()
+            .register_type::<EntityGridSettings>()
+            .register_type::<EntityGridState>()
+            .register_type::<GridCursor>()
+            .register_type::<GridPreview>()
+            .register_type::<GridStreamAnchor>();
 
         app.add_systems(
             Update,
-            |added_entities: Query<Entity, Added<GridPosition>>,
-             mut query_common: Query<(&mut Transform, &GridPosition)>,
-             mut state: ResMut<EntityGridState>| {
-                added_entities.iter().for_each(|incoming_entity| {
-                    // Get the position of the entity
-                    let mut scoped_query = match query_common.get_mut(incoming_entity) {
-                        Ok(transform) => transform,
-                        Err(_) => return,
-                    };
-                    // Spawn rotation
-                    let spawn_rotation = state.spawn_rotation;
-
-                    // Set the translation of the entity based on the position of the position
-                    scoped_query.0.translation = Vec3::new(
-                        scoped_query.1.x as f32 * state.settings.cell_size,
-                        state.settings.up_offset,
-                        scoped_query.1.y as f32 * state.settings.cell_size,
-                    );
-                    // Check if the position is already in the grid
-                    match state.grid.data.get_mut(scoped_query.1) {
-                        // If the position is already in the grid, update the entity
-                        Some(entity) => {
-                            // Set the rotation of the entity based on the rotation of the position
-                            scoped_query.0.rotation =
-                                Quat::from_rotation_y(entity.rotation.to_angle());
-                            *entity = GridEntity {
-                                entity: incoming_entity,
-                                rotation: spawn_rotation,
-                            };
-                        }
-                        // If the position is not in the grid, insert the entity
-                        None => {
-                            // Set the rotation of the entity based on the rotation of the position
-                            scoped_query.0.rotation =
-                                Quat::from_rotation_y(state.spawn_rotation.to_angle());
-                            state.grid.data.insert(scoped_query.1.clone(), GridEntity {
-                                entity: incoming_entity,
-                                rotation: spawn_rotation,
-                            });
-                        }
-                    }
-                });
-            },
+            (
+                motion::advance_grid_motions,
+                systems::clear_removed_positions,
+                systems::requeue_removed_placements,
+                systems::sync_grid_positions,
+                systems::sync_grid_rotations,
+                systems::sync_hex_facings,
+                systems::sync_grid_tags,
+                autotile::update_autotiles,
+                motion::animate_grid_motions.run_if(resource_exists::<Time>),
+                voxels::clear_removed_voxels,
+                voxels::sync_voxel_positions,
+                voxels::sync_voxel_rotations,
+                fog::update_grid_viewers,
+                fog::apply_fog_visibility,
+                streaming::stream_grid_chunks,
+                cursor::update_grid_cursor,
+                placement::update_grid_previews,
+            )
+                .chain(),
         );
     }
 }
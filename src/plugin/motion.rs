@@ -0,0 +1,304 @@
+use std::{collections::VecDeque, f32::consts::FRAC_PI_2};
+
+use bevy::prelude::*;
+
+pub mod prelude {
+    pub use super::GridMotion;
+}
+
+use crate::prelude::*;
+
+/// Animates the `Transform` of a placed entity between cells instead of teleporting it
+///
+/// The entity is registered in its destination as soon as its `GridPosition` changes, and the
+/// destination stays reserved during the move: other entities entering it are rejected, whatever
+/// the `OccupancyPolicy`. Waypoints are visited one after the other, a rejected waypoint clears
+/// the remaining ones. A `MotionFinished` event is sent whenever the entity reaches a cell.
+#[derive(Debug, Clone, PartialEq, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct GridMotion {
+    /// The cells travelled per second, a quarter turn takes as long as a cell
+    pub speed: f32,
+    /// The easing of every move
+    pub easing: EaseFunction,
+    /// The cells to visit next, in order
+    pub waypoints: VecDeque<GridPosition>,
+    tween: Option<Tween>,
+    shown: Option<Transform>,
+    destination: Option<GridPosition>,
+}
+
+/// A move between two transforms
+#[derive(Debug, Copy, Clone, PartialEq, Reflect)]
+struct Tween {
+    from: Transform,
+    to: Transform,
+    elapsed: f32,
+    duration: f32,
+}
+
+impl Default for GridMotion {
+    fn default() -> Self {
+        Self::new(4.0)
+    }
+}
+
+impl GridMotion {
+    /// Move linearly at the given number of cells per second
+    pub fn new(speed: f32) -> Self {
+        Self {
+            speed,
+            easing: EaseFunction::Linear,
+            waypoints: VecDeque::new(),
+            tween: None,
+            shown: None,
+            destination: None,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: EaseFunction) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Queue cells to visit after the current ones
+    pub fn with_waypoints(mut self, waypoints: impl IntoIterator<Item = GridPosition>) -> Self {
+        self.waypoints.extend(waypoints);
+        self
+    }
+
+    /// Queue a cell to visit after the current ones
+    pub fn push(&mut self, waypoint: GridPosition) {
+        self.waypoints.push_back(waypoint);
+    }
+
+    /// Whether the entity is on its way to a cell
+    pub fn is_moving(&self) -> bool {
+        self.tween.is_some() || self.destination.is_some()
+    }
+
+    /// Get the progress of the current move, from 0 to 1, `None` if the entity is not moving
+    pub fn progress(&self) -> Option<f32> {
+        self.tween.map(|tween| {
+            if tween.duration > 0.0 {
+                (tween.elapsed / tween.duration).min(1.0)
+            } else {
+                1.0
+            }
+        })
+    }
+}
+
+/// Move idle entities to their next waypoint
+pub fn advance_grid_motions(mut movers: Query<(&mut GridMotion, &mut GridPosition)>) {
+    for (mut motion, mut position) in &mut movers {
+        if motion.is_moving() {
+            continue;
+        }
+        if let Some(waypoint) = motion.waypoints.pop_front() {
+            motion.destination = Some(waypoint);
+            *position = waypoint;
+        }
+    }
+}
+
+/// Tween the `Transform` of moving entities towards the one written by their placement
+pub fn animate_grid_motions(
+    time: Res<Time>,
+    mut movers: Query<(Entity, &mut GridMotion, &mut Transform, &GridPosition)>,
+    mut finished: EventWriter<MotionFinished>,
+    grids: Grids,
+) {
+    for (entity, mut motion, mut transform, position) in &mut movers {
+        let Some(shown) = motion.shown else {
+            // The first placement is not animated
+            motion.shown = Some(*transform);
+            continue;
+        };
+
+        if *transform != shown {
+            // The placement moved or turned the entity, tween from what is shown towards it
+            let cell_size = grids
+                .find(entity)
+                .and_then(|grid| grids.state(grid))
+                .map_or(1.0, |state| state.settings.cell_size);
+            let cells = shown.translation.distance(transform.translation) / cell_size;
+            let turns = shown.rotation.angle_between(transform.rotation) / FRAC_PI_2;
+            let duration = if motion.speed > 0.0 {
+                cells.max(turns) / motion.speed
+            } else {
+                0.0
+            };
+            motion.tween = Some(Tween {
+                from: shown,
+                to: *transform,
+                elapsed: 0.0,
+                duration,
+            });
+            motion.destination.get_or_insert(*position);
+        }
+
+        let easing = motion.easing;
+        if let Some(tween) = &mut motion.tween {
+            tween.elapsed += time.delta_secs();
+            let t = if tween.duration > 0.0 {
+                (tween.elapsed / tween.duration).min(1.0)
+            } else {
+                1.0
+            };
+            let eased = EasingCurve::new(0.0, 1.0, easing).sample_clamped(t);
+            transform.translation = tween.from.translation.lerp(tween.to.translation, eased);
+            transform.rotation = tween.from.rotation.slerp(tween.to.rotation, eased);
+            if t >= 1.0 {
+                transform.translation = tween.to.translation;
+                transform.rotation = tween.to.rotation;
+                motion.tween = None;
+            }
+        }
+        motion.shown = Some(*transform);
+
+        if motion.tween.is_none()
+            && let Some(destination) = motion.destination.take()
+        {
+            if *position == destination {
+                finished.send(MotionFinished {
+                    entity,
+                    position: *position,
+                    remaining: motion.waypoints.len(),
+                });
+            } else {
+                // The waypoint was rejected, give up on the path
+                motion.waypoints.clear();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn advance(app: &mut App, seconds: f32) {
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        app.update();
+    }
+
+    #[test]
+    fn test_grid_motion() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        app.init_resource::<Time>();
+        app.world_mut()
+            .resource_mut::<EntityGridState>()
+            .settings
+            .occupancy = OccupancyPolicy::Reject;
+
+        let mover = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GridPosition::new(0, 0),
+                GridMotion::new(2.0),
+            ))
+            .id();
+        let other = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(1, 1)))
+            .id();
+        app.update();
+
+        app.world_mut()
+            .get_mut::<GridMotion>(mover)
+            .unwrap()
+            .waypoints
+            .extend([GridPosition::new(1, 0), GridPosition::new(2, 0)]);
+        advance(&mut app, 0.25);
+        // Halfway through, the destination is already reserved
+        assert_eq!(
+            app.world().get::<Transform>(mover).unwrap().translation,
+            Vec3::new(0.5, 0.0, 0.0)
+        );
+        let state = app.world().resource::<EntityGridState>();
+        assert_eq!(
+            state
+                .get(GridLayer::DEFAULT, GridPosition::new(1, 0))
+                .map(|entry| entry.entity),
+            Some(mover)
+        );
+        *app.world_mut().get_mut::<GridPosition>(other).unwrap() = GridPosition::new(1, 0);
+        advance(&mut app, 0.25);
+        assert_eq!(
+            *app.world().get::<GridPosition>(other).unwrap(),
+            GridPosition::new(1, 1)
+        );
+
+        assert_eq!(
+            app.world().get::<Transform>(mover).unwrap().translation,
+            Vec3::new(1.0, 0.0, 0.0)
+        );
+        let finished = app.world().resource::<Events<MotionFinished>>();
+        assert_eq!(
+            finished.iter_current_update_events().collect::<Vec<_>>(),
+            vec![&MotionFinished {
+                entity: mover,
+                position: GridPosition::new(1, 0),
+                remaining: 1,
+            }]
+        );
+
+        advance(&mut app, 0.5);
+        assert_eq!(
+            *app.world().get::<GridPosition>(mover).unwrap(),
+            GridPosition::new(2, 0)
+        );
+        assert!(!app.world().get::<GridMotion>(mover).unwrap().is_moving());
+    }
+
+    #[test]
+    fn test_grid_motion_reservation() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        app.init_resource::<Time>();
+
+        let mover = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GridPosition::new(0, 0),
+                GridMotion::new(1.0),
+            ))
+            .id();
+        let other = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(1, 1)))
+            .id();
+        app.update();
+
+        app.world_mut()
+            .get_mut::<GridMotion>(mover)
+            .unwrap()
+            .push(GridPosition::new(1, 0));
+        advance(&mut app, 0.25);
+        assert!(app.world().get::<GridMotion>(mover).unwrap().is_moving());
+
+        // The default `Replace` policy doesn't despawn a mover on its way
+        *app.world_mut().get_mut::<GridPosition>(other).unwrap() = GridPosition::new(1, 0);
+        advance(&mut app, 0.25);
+        assert!(app.world().get_entity(mover).is_ok());
+        assert_eq!(
+            *app.world().get::<GridPosition>(other).unwrap(),
+            GridPosition::new(1, 1)
+        );
+        let state = app.world().resource::<EntityGridState>();
+        assert_eq!(
+            state
+                .get(GridLayer::DEFAULT, GridPosition::new(1, 0))
+                .map(|entry| entry.entity),
+            Some(mover)
+        );
+    }
+}
@@ -0,0 +1,667 @@
+use std::{fmt, sync::Arc};
+
+use bevy::prelude::*;
+
+pub mod prelude {
+    pub use super::{
+        GridPlacementRules, GridPreview, PlacementContext, PlacementDenied, PlacementRequest,
+        PlacementRule, PreviewTarget,
+    };
+}
+
+use crate::prelude::*;
+
+/// A placement to validate before spawning
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacementRequest {
+    /// The anchor cell
+    pub position: GridPosition,
+    /// The cells covered around the anchor
+    pub footprint: GridFootprint,
+    /// The rotation turning the footprint
+    pub rotation: Rotation,
+    /// The layer to place on
+    pub layer: GridLayer,
+    /// The entity being moved, whose own cells don't block it
+    pub entity: Option<Entity>,
+}
+
+impl PlacementRequest {
+    /// Request a single cell on the default layer
+    pub fn new(position: GridPosition) -> Self {
+        Self {
+            position,
+            footprint: GridFootprint::single(),
+            rotation: Rotation::default(),
+            layer: GridLayer::DEFAULT,
+            entity: None,
+        }
+    }
+
+    pub fn with_footprint(mut self, footprint: GridFootprint) -> Self {
+        self.footprint = footprint;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_layer(mut self, layer: GridLayer) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    /// Validate moving an existing entity, ignoring the cells it already covers
+    pub fn with_entity(mut self, entity: Entity) -> Self {
+        self.entity = Some(entity);
+        self
+    }
+}
+
+/// What a placement rule checks against
+pub struct PlacementContext<'a> {
+    /// The state of the grid
+    pub state: &'a EntityGridState,
+    /// The validated request
+    pub request: &'a PlacementRequest,
+    /// The cells covered by the turned footprint
+    pub cells: Vec<GridPosition>,
+}
+
+impl PlacementContext<'_> {
+    /// Get the occupant of a cell on the requested layer, ignoring the requesting entity
+    pub fn occupant(&self, position: GridPosition) -> Option<GridEntity> {
+        self.state
+            .get(self.request.layer, position)
+            .filter(|entry| Some(entry.entity) != self.request.entity)
+    }
+
+    /// Iterate over the cells next to the footprint, sharing an edge with a covered cell
+    pub fn adjacent_cells(&self) -> impl Iterator<Item = GridPosition> + '_ {
+        let mut adjacent = Vec::new();
+        for cell in &self.cells {
+            for offset in [IVec2::Y, IVec2::X, IVec2::NEG_Y, IVec2::NEG_X] {
+                let neighbor = *cell + offset;
+                if !self.cells.contains(&neighbor) && !adjacent.contains(&neighbor) {
+                    adjacent.push(neighbor);
+                }
+            }
+        }
+        adjacent.into_iter()
+    }
+}
+
+/// Why a placement was refused
+#[derive(Debug, Clone, PartialEq, Eq, Reflect)]
+pub enum PlacementDenied {
+    /// A covered cell is outside of the grid
+    OutOfBounds(GridPosition),
+    /// A covered cell is taken by another entity
+    Occupied {
+        position: GridPosition,
+        occupant: Entity,
+    },
+    /// A covered cell is not on suitable terrain
+    Terrain(GridPosition),
+    /// No cell next to the footprint meets the adjacency requirement
+    NotAdjacent,
+    /// A custom rule refused the placement
+    Rule(String),
+    /// The grid entity has no `EntityGridState`
+    NoGridState(Entity),
+}
+
+impl fmt::Display for PlacementDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfBounds(position) => {
+                write!(f, "cell ({}, {}) is out of bounds", position.x, position.y)
+            }
+            Self::Occupied { position, occupant } => write!(
+                f,
+                "cell ({}, {}) is occupied by {occupant}",
+                position.x, position.y
+            ),
+            Self::Terrain(position) => {
+                write!(
+                    f,
+                    "cell ({}, {}) has unsuitable terrain",
+                    position.x, position.y
+                )
+            }
+            Self::NotAdjacent => write!(f, "no neighbor meets the adjacency requirement"),
+            Self::Rule(reason) => write!(f, "{reason}"),
+            Self::NoGridState(grid) => write!(f, "{grid} has no EntityGridState"),
+        }
+    }
+}
+
+impl std::error::Error for PlacementDenied {}
+
+/// The signature of a placement rule
+pub type PlacementRuleFn = dyn Fn(&PlacementContext) -> Result<(), PlacementDenied> + Send + Sync;
+
+/// A check a placement has to pass
+#[derive(Clone)]
+pub struct PlacementRule(Arc<PlacementRuleFn>);
+
+impl PlacementRule {
+    /// A rule from a custom check
+    pub fn new(
+        check: impl Fn(&PlacementContext) -> Result<(), PlacementDenied> + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(check))
+    }
+
+    /// Every covered cell is within the bounds of the storage of the layer
+    pub fn in_bounds() -> Self {
+        Self::new(|context| {
+            let Some(grid) = context.state.layer(context.request.layer) else {
+                return Ok(());
+            };
+            match context.cells.iter().find(|cell| !grid.in_bounds(**cell)) {
+                Some(cell) => Err(PlacementDenied::OutOfBounds(*cell)),
+                None => Ok(()),
+            }
+        })
+    }
+
+    /// Every covered cell is within a rectangle, both corners included
+    pub fn within(min: GridPosition, max: GridPosition) -> Self {
+        Self::new(move |context| {
+            let outside = context.cells.iter().find(|cell| {
+                !(min.x..=max.x).contains(&cell.x) || !(min.y..=max.y).contains(&cell.y)
+            });
+            match outside {
+                Some(cell) => Err(PlacementDenied::OutOfBounds(*cell)),
+                None => Ok(()),
+            }
+        })
+    }
+
+    /// Every covered cell is free, or taken by the requesting entity
+    pub fn unoccupied() -> Self {
+        Self::new(|context| {
+            for cell in &context.cells {
+                if let Some(occupant) = context.occupant(*cell) {
+                    return Err(PlacementDenied::Occupied {
+                        position: *cell,
+                        occupant: occupant.entity,
+                    });
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Every covered cell is free, or can be entered under the `OccupancyPolicy` of the grid
+    ///
+    /// This refuses the same placements as `sync_grid_positions`: occupied cells under `Reject`,
+    /// multi-cell placements that don't `Replace`, and single cells that would stack on or swap
+    /// a multi-cell occupant. `Nudge` is checked at the requested cell, not the nudged one.
+    pub fn occupancy() -> Self {
+        Self::new(|context| {
+            let policy = context.state.settings.occupancy;
+            let multi_cell = context.cells.len() > 1;
+            for cell in &context.cells {
+                let Some(occupant) = context.occupant(*cell) else {
+                    continue;
+                };
+                let refused = match policy {
+                    OccupancyPolicy::Replace => false,
+                    OccupancyPolicy::Reject => true,
+                    OccupancyPolicy::Stack | OccupancyPolicy::Swap => {
+                        multi_cell || context.state.footprints.contains_key(&occupant.entity)
+                    }
+                    OccupancyPolicy::Nudge => multi_cell,
+                };
+                if refused {
+                    return Err(PlacementDenied::Occupied {
+                        position: *cell,
+                        occupant: occupant.entity,
+                    });
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Every covered cell sits on an entry of a terrain layer accepted by the predicate
+    pub fn on_terrain(
+        terrain: GridLayer,
+        predicate: impl Fn(GridEntity) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self::new(move |context| {
+            let unsuitable = context
+                .cells
+                .iter()
+                .find(|cell| !context.state.get(terrain, **cell).is_some_and(&predicate));
+            match unsuitable {
+                Some(cell) => Err(PlacementDenied::Terrain(*cell)),
+                None => Ok(()),
+            }
+        })
+    }
+
+    /// A cell next to the footprint holds an entry of a layer accepted by the predicate
+    pub fn adjacent_to(
+        layer: GridLayer,
+        predicate: impl Fn(GridEntity) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self::new(move |context| {
+            let adjacent = context
+                .adjacent_cells()
+                .any(|cell| context.state.get(layer, cell).is_some_and(&predicate));
+            if adjacent {
+                Ok(())
+            } else {
+                Err(PlacementDenied::NotAdjacent)
+            }
+        })
+    }
+
+    /// Run the rule
+    pub fn check(&self, context: &PlacementContext) -> Result<(), PlacementDenied> {
+        (self.0)(context)
+    }
+}
+
+impl fmt::Debug for PlacementRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PlacementRule").finish_non_exhaustive()
+    }
+}
+
+/// The rules checked by `Grids::can_place`, the `GridPreview`s and every placement
+///
+/// By default a placement has to be in bounds and allowed by the `OccupancyPolicy`. Rules run in
+/// order and the first refusal is reported. Entities whose placement is refused are rejected like
+/// under `OccupancyPolicy::Reject`.
+#[derive(Debug, Clone, Resource)]
+pub struct GridPlacementRules {
+    pub rules: Vec<PlacementRule>,
+}
+
+impl Default for GridPlacementRules {
+    fn default() -> Self {
+        Self {
+            rules: vec![PlacementRule::in_bounds(), PlacementRule::occupancy()],
+        }
+    }
+}
+
+impl GridPlacementRules {
+    /// No rules, every placement is accepted
+    pub fn empty() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Add a rule, checked after the existing ones
+    pub fn with(mut self, rule: PlacementRule) -> Self {
+        self.add(rule);
+        self
+    }
+
+    /// Add a rule, checked after the existing ones
+    pub fn add(&mut self, rule: PlacementRule) -> &mut Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Check a placement against every rule
+    pub fn check(
+        &self,
+        state: &EntityGridState,
+        request: &PlacementRequest,
+    ) -> Result<(), PlacementDenied> {
+        let context = PlacementContext {
+            state,
+            request,
+            cells: request
+                .footprint
+                .cells_at(request.position, request.rotation)
+                .collect(),
+        };
+        self.rules.iter().try_for_each(|rule| rule.check(&context))
+    }
+}
+
+/// Where a `GridPreview` is shown
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Reflect)]
+pub enum PreviewTarget {
+    /// The cell under the `GridCursor`, if the cursor picks the grid of the preview
+    #[default]
+    Cursor,
+    /// A fixed cell
+    Cell(GridPosition),
+}
+
+/// A ghost of an entity to place, snapped to a cell and validated with the `GridPlacementRules`
+///
+/// The preview is never registered in the grid. Its `GridFootprint`, if any, is validated and
+/// centred like a placed entity. Read `position` and `denied`, for instance to tint the ghost.
+/// The `Visibility` of the ghost, if any, is hidden while it has no position.
+#[derive(Debug, Default, Clone, PartialEq, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct GridPreview {
+    /// The grid to preview in, `None` for the default grid
+    pub grid: Option<Entity>,
+    /// The layer to preview on
+    pub layer: GridLayer,
+    /// The rotation of the ghost
+    pub rotation: Rotation,
+    /// Where to show the ghost
+    pub target: PreviewTarget,
+    /// The cell the ghost snapped to, `None` if the target is off the grid
+    pub position: Option<GridPosition>,
+    /// Why the ghost can't be placed, `None` if it can
+    pub denied: Option<PlacementDenied>,
+}
+
+impl GridPreview {
+    /// Whether the ghost is on a cell it can be placed at
+    pub fn is_valid(&self) -> bool {
+        self.position.is_some() && self.denied.is_none()
+    }
+}
+
+/// The `GridPreview`s to snap, with their `Visibility` if they're drawn
+type PreviewQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut GridPreview,
+        &'static mut Transform,
+        Option<&'static mut Visibility>,
+        Option<&'static GridFootprint>,
+    ),
+>;
+
+/// Snap the `GridPreview`s to their target and validate them
+pub fn update_grid_previews(cursor: Res<GridCursor>, mut previews: PreviewQuery, grids: Grids) {
+    for (mut preview, mut transform, visibility, footprint) in &mut previews {
+        let Some(state) = grids.state(preview.grid) else {
+            continue;
+        };
+        let position = match preview.target {
+            PreviewTarget::Cursor => cursor.position.filter(|_| cursor.grid == preview.grid),
+            PreviewTarget::Cell(position) => Some(position),
+        };
+        let footprint = footprint.cloned().unwrap_or_default();
+
+        let mut denied = None;
+        if let Some(position) = position {
+            let translation = state.settings.footprint_to_world(
+                position,
+                &footprint,
+                preview.rotation,
+                preview.layer,
+            );
+            let rotation = state.settings.rotation(preview.rotation);
+            let placed = grids
+                .origin(preview.grid)
+                .mul_transform(Transform::from_translation(translation).with_rotation(rotation));
+            transform.translation = placed.translation;
+            transform.rotation = placed.rotation;
+
+            let request = PlacementRequest::new(position)
+                .with_footprint(footprint)
+                .with_rotation(preview.rotation)
+                .with_layer(preview.layer);
+            denied = grids.can_place(preview.grid, &request).err();
+        }
+
+        if let Some(mut visibility) = visibility {
+            visibility.set_if_neq(match position {
+                Some(_) => Visibility::Inherited,
+                None => Visibility::Hidden,
+            });
+        }
+        if preview.position != position || preview.denied != denied {
+            preview.position = position;
+            preview.denied = denied;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    const TERRAIN: GridLayer = GridLayer(1);
+
+    #[test]
+    fn test_placement_rules() {
+        let mut state = EntityGridState::new(EntityGridSettings::default());
+        let rock = Entity::from_raw(1);
+        let grass = Entity::from_raw(2);
+        state
+            .grid
+            .insert(GridPosition::new(2, 0), rock, Rotation::Up);
+        for x in 0..2 {
+            for y in 0..3 {
+                state
+                    .layer_mut(TERRAIN)
+                    .insert(GridPosition::new(x, y), grass, Rotation::Up);
+            }
+        }
+        let footprint = GridFootprint::rectangle(2, 3);
+        let building = |position| PlacementRequest::new(position).with_footprint(footprint.clone());
+
+        // The default rules follow the occupancy policy
+        let rules = GridPlacementRules::default();
+        assert_eq!(
+            rules.check(&state, &building(GridPosition::new(0, 0))),
+            Ok(())
+        );
+        assert_eq!(
+            rules.check(&state, &building(GridPosition::new(1, 0))),
+            Ok(())
+        );
+        state.settings.occupancy = OccupancyPolicy::Stack;
+        assert_eq!(
+            rules.check(&state, &building(GridPosition::new(1, 0))),
+            Err(PlacementDenied::Occupied {
+                position: GridPosition::new(2, 0),
+                occupant: rock,
+            })
+        );
+        assert_eq!(
+            rules.check(&state, &PlacementRequest::new(GridPosition::new(2, 0))),
+            Ok(())
+        );
+        // Turned upside down, the footprint is clear of the rock
+        assert_eq!(
+            rules.check(
+                &state,
+                &building(GridPosition::new(1, 0)).with_rotation(Rotation::Down)
+            ),
+            Ok(())
+        );
+
+        let rules = GridPlacementRules::default()
+            .with(PlacementRule::on_terrain(TERRAIN, move |entry| {
+                entry.entity == grass
+            }))
+            .with(PlacementRule::adjacent_to(
+                GridLayer::DEFAULT,
+                move |entry| entry.entity == rock,
+            ));
+        assert_eq!(
+            rules.check(&state, &building(GridPosition::new(0, 0))),
+            Ok(())
+        );
+        assert_eq!(
+            rules.check(&state, &building(GridPosition::new(0, 1))),
+            Err(PlacementDenied::Terrain(GridPosition::new(0, 3)))
+        );
+        assert_eq!(
+            rules.check(&state, &PlacementRequest::new(GridPosition::new(0, 0))),
+            Err(PlacementDenied::NotAdjacent)
+        );
+
+        let rules = GridPlacementRules::empty()
+            .with(PlacementRule::within(
+                GridPosition::new(0, 0),
+                GridPosition::new(1, 1),
+            ))
+            .with(PlacementRule::new(|context| {
+                if context.request.rotation == Rotation::Down {
+                    Err(PlacementDenied::Rule("upside down".to_string()))
+                } else {
+                    Ok(())
+                }
+            }));
+        let request = PlacementRequest::new(GridPosition::new(1, 1)).with_rotation(Rotation::Down);
+        assert_eq!(
+            rules.check(&state, &request),
+            Err(PlacementDenied::Rule("upside down".to_string()))
+        );
+        // Turned upside down, the footprint reaches below the rectangle
+        assert_eq!(
+            rules.check(&state, &request.with_footprint(footprint)),
+            Err(PlacementDenied::OutOfBounds(GridPosition::new(1, -1)))
+        );
+    }
+
+    #[test]
+    fn test_grid_preview() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        app.world_mut()
+            .resource_mut::<EntityGridState>()
+            .settings
+            .occupancy = OccupancyPolicy::Reject;
+        app.world_mut()
+            .spawn((Transform::default(), GridPosition::new(2, 0)));
+        let preview = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                Visibility::default(),
+                GridFootprint::rectangle(2, 1),
+                GridPreview {
+                    target: PreviewTarget::Cell(GridPosition::new(0, 0)),
+                    ..default()
+                },
+            ))
+            .id();
+        app.update();
+
+        let ghost = app.world().get::<GridPreview>(preview).unwrap();
+        assert!(ghost.is_valid());
+        assert_eq!(ghost.position, Some(GridPosition::new(0, 0)));
+        assert_eq!(
+            app.world().get::<Transform>(preview).unwrap().translation,
+            Vec3::new(0.5, 0.0, 0.0)
+        );
+        // The preview is not registered in the grid
+        assert_eq!(app.world().resource::<EntityGridState>().grid.len(), 1);
+
+        app.world_mut()
+            .get_mut::<GridPreview>(preview)
+            .unwrap()
+            .target = PreviewTarget::Cell(GridPosition::new(1, 0));
+        app.update();
+        let ghost = app.world().get::<GridPreview>(preview).unwrap();
+        assert!(!ghost.is_valid());
+        assert!(matches!(
+            ghost.denied,
+            Some(PlacementDenied::Occupied { .. })
+        ));
+
+        // The cursor is off the grid without a window
+        app.world_mut()
+            .get_mut::<GridPreview>(preview)
+            .unwrap()
+            .target = PreviewTarget::Cursor;
+        app.update();
+        let ghost = app.world().get::<GridPreview>(preview).unwrap();
+        assert_eq!(ghost.position, None);
+        assert!(!ghost.is_valid());
+        assert_eq!(
+            app.world().get::<Visibility>(preview),
+            Some(&Visibility::Hidden)
+        );
+        app.world_mut()
+            .get_mut::<GridPreview>(preview)
+            .unwrap()
+            .target = PreviewTarget::Cell(GridPosition::new(0, 0));
+        app.update();
+        assert_eq!(
+            app.world().get::<Visibility>(preview),
+            Some(&Visibility::Inherited)
+        );
+
+        // The registered rules are used outside of the previews too
+        app.world_mut()
+            .resource_mut::<GridPlacementRules>()
+            .add(PlacementRule::within(
+                GridPosition::new(0, 0),
+                GridPosition::new(1, 1),
+            ));
+        let request = PlacementRequest::new(GridPosition::new(1, 0))
+            .with_footprint(GridFootprint::rectangle(2, 1))
+            .with_rotation(Rotation::Down);
+        let placed = app
+            .world_mut()
+            .run_system_once(move |grids: Grids| grids.can_place(None, &request))
+            .unwrap();
+        assert_eq!(placed, Ok(()));
+        let request = PlacementRequest::new(GridPosition::new(1, 1))
+            .with_footprint(GridFootprint::rectangle(2, 1));
+        let placed = app
+            .world_mut()
+            .run_system_once(move |grids: Grids| grids.can_place(None, &request))
+            .unwrap();
+        assert_eq!(
+            placed,
+            Err(PlacementDenied::OutOfBounds(GridPosition::new(2, 1)))
+        );
+    }
+
+    #[test]
+    fn test_enforce_placement_rules() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        app.world_mut()
+            .resource_mut::<GridPlacementRules>()
+            .add(PlacementRule::within(
+                GridPosition::new(0, 0),
+                GridPosition::new(3, 3),
+            ));
+        let inside = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(1, 1)))
+            .id();
+        let outside = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(5, 5)))
+            .id();
+        app.update();
+        assert!(app.world().get::<GridPosition>(outside).is_none());
+
+        // Moving out of the rectangle is rejected, moving within it is not
+        *app.world_mut().get_mut::<GridPosition>(inside).unwrap() = GridPosition::new(4, 1);
+        app.update();
+        assert_eq!(
+            *app.world().get::<GridPosition>(inside).unwrap(),
+            GridPosition::new(1, 1)
+        );
+        *app.world_mut().get_mut::<GridPosition>(inside).unwrap() = GridPosition::new(3, 1);
+        app.update();
+        let state = app.world().resource::<EntityGridState>();
+        assert_eq!(
+            state
+                .grid
+                .get(GridPosition::new(3, 1))
+                .map(|entry| entry.entity),
+            Some(inside)
+        );
+        assert_eq!(state.grid.len(), 1);
+    }
+}
@@ -0,0 +1,324 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub mod prelude {
+    #[cfg(feature = "remote")]
+    pub use super::EntityGridRemoteExt;
+    pub use super::{
+        BRP_GET_CELL_METHOD, BRP_NEIGHBORS_METHOD, BRP_PLACE_METHOD, BrpGetCellParams,
+        BrpNeighborQuery, BrpNeighborsParams, BrpPlaceParams, GridRemoteError,
+    };
+}
+
+use crate::prelude::*;
+
+/// The BRP method returning the entry of a cell
+pub const BRP_GET_CELL_METHOD: &str = "entity_grid/get_cell";
+
+/// The BRP method placing an entity on a cell
+pub const BRP_PLACE_METHOD: &str = "entity_grid/place";
+
+/// The BRP method returning the occupied neighbors of a cell
+pub const BRP_NEIGHBORS_METHOD: &str = "entity_grid/neighbors";
+
+/// The parameters of `entity_grid/get_cell`
+///
+/// Returns the `GridEntity` occupying the cell, or `null` for empty cells.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BrpGetCellParams {
+    /// The grid entity, the default grid if omitted
+    #[serde(default)]
+    pub grid: Option<Entity>,
+    /// The layer, the default layer if omitted
+    #[serde(default)]
+    pub layer: GridLayer,
+    /// The cell
+    pub position: GridPosition,
+}
+
+impl BrpGetCellParams {
+    /// Get the entry of the cell
+    pub fn run(&self, world: &World) -> Result<Option<GridEntity>, GridRemoteError> {
+        Ok(grid_state(world, self.grid)?.get(self.layer, self.position))
+    }
+}
+
+/// The parameters of `entity_grid/place`
+///
+/// Inserts the grid components on the entity, which is placed on the next update
+/// following the occupancy policy of the grid. Returns `null`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BrpPlaceParams {
+    /// The entity to place
+    pub entity: Entity,
+    /// The grid entity, the default grid if omitted
+    #[serde(default)]
+    pub grid: Option<Entity>,
+    /// The layer, the current layer of the entity if omitted
+    #[serde(default)]
+    pub layer: Option<GridLayer>,
+    /// The cell
+    pub position: GridPosition,
+    /// The rotation, the current rotation of the entity if omitted
+    #[serde(default)]
+    pub rotation: Option<Rotation>,
+}
+
+impl BrpPlaceParams {
+    /// Insert the grid components on the entity
+    pub fn run(&self, world: &mut World) -> Result<(), GridRemoteError> {
+        grid_state(world, self.grid)?;
+        let mut entity = world
+            .get_entity_mut(self.entity)
+            .map_err(|_| GridRemoteError::MissingEntity(self.entity))?;
+
+        if entity.get::<Transform>().is_none() {
+            entity.insert(Transform::default());
+        }
+        match self.grid {
+            Some(grid) => {
+                entity.insert(InGrid(grid));
+            }
+            None => {
+                entity.remove::<InGrid>();
+            }
+        }
+        if let Some(layer) = self.layer {
+            entity.insert(layer);
+        }
+        if let Some(rotation) = self.rotation {
+            entity.insert(GridRotation(rotation));
+        }
+        entity.insert(self.position);
+        Ok(())
+    }
+}
+
+/// The neighbors returned by `entity_grid/neighbors`
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrpNeighborQuery {
+    /// The four cardinal neighbors
+    #[default]
+    Cardinal,
+    /// The four ordinal neighbors
+    Ordinal,
+    /// The cells of a radius query
+    Radius(RadiusQuery),
+}
+
+/// The parameters of `entity_grid/neighbors`
+///
+/// Returns the occupied neighbors as a list of `Neighbor`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BrpNeighborsParams {
+    /// The grid entity, the default grid if omitted
+    #[serde(default)]
+    pub grid: Option<Entity>,
+    /// The layer, the default layer if omitted
+    #[serde(default)]
+    pub layer: GridLayer,
+    /// The cell
+    pub position: GridPosition,
+    /// The neighbors to return, the cardinal neighbors if omitted
+    #[serde(default)]
+    pub query: BrpNeighborQuery,
+}
+
+impl BrpNeighborsParams {
+    /// Get the occupied neighbors of the cell
+    pub fn run(&self, world: &World) -> Result<Vec<Neighbor>, GridRemoteError> {
+        let state = grid_state(world, self.grid)?;
+        Ok(match self.query {
+            BrpNeighborQuery::Cardinal => {
+                let neighbors = state.get_cardinal_neighbors(self.position, self.layer);
+                [
+                    neighbors.north,
+                    neighbors.east,
+                    neighbors.south,
+                    neighbors.west,
+                ]
+                .into_iter()
+                .flatten()
+                .collect()
+            }
+            BrpNeighborQuery::Ordinal => {
+                let neighbors = state.get_ordinal_neighbors(self.position, self.layer);
+                [
+                    neighbors.north_west,
+                    neighbors.north_east,
+                    neighbors.south_east,
+                    neighbors.south_west,
+                ]
+                .into_iter()
+                .flatten()
+                .collect()
+            }
+            BrpNeighborQuery::Radius(query) => {
+                state
+                    .get_radius_neighbors(self.position, query, self.layer)
+                    .neighbors
+            }
+        })
+    }
+}
+
+/// An error while handling a grid request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridRemoteError {
+    /// The grid entity has no `EntityGridState`
+    MissingGrid(Entity),
+    /// The entity does not exist
+    MissingEntity(Entity),
+}
+
+impl fmt::Display for GridRemoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingGrid(grid) => write!(f, "entity {grid} has no grid state"),
+            Self::MissingEntity(entity) => write!(f, "entity {entity} does not exist"),
+        }
+    }
+}
+
+impl std::error::Error for GridRemoteError {}
+
+fn grid_state(world: &World, grid: Option<Entity>) -> Result<&EntityGridState, GridRemoteError> {
+    match grid {
+        Some(grid) => world
+            .get::<EntityGridState>(grid)
+            .ok_or(GridRemoteError::MissingGrid(grid)),
+        None => Ok(world.resource::<EntityGridState>()),
+    }
+}
+
+#[cfg(feature = "remote")]
+pub use brp::*;
+
+#[cfg(feature = "remote")]
+mod brp {
+    use bevy::remote::{BrpError, BrpResult, RemotePlugin, error_codes};
+    use serde::de::DeserializeOwned;
+    use serde_json::Value;
+
+    use super::*;
+
+    /// Adds the grid methods to the `RemotePlugin`
+    pub trait EntityGridRemoteExt {
+        /// Add `entity_grid/get_cell`, `entity_grid/place` and `entity_grid/neighbors`
+        fn with_entity_grid_methods(self) -> Self;
+    }
+
+    impl EntityGridRemoteExt for RemotePlugin {
+        fn with_entity_grid_methods(self) -> Self {
+            self.with_method(BRP_GET_CELL_METHOD, process_get_cell_request)
+                .with_method(BRP_PLACE_METHOD, process_place_request)
+                .with_method(BRP_NEIGHBORS_METHOD, process_neighbors_request)
+        }
+    }
+
+    impl From<GridRemoteError> for BrpError {
+        fn from(error: GridRemoteError) -> Self {
+            let code = match error {
+                GridRemoteError::MissingGrid(_) => error_codes::COMPONENT_NOT_PRESENT,
+                GridRemoteError::MissingEntity(_) => error_codes::ENTITY_NOT_FOUND,
+            };
+            BrpError {
+                code,
+                message: error.to_string(),
+                data: None,
+            }
+        }
+    }
+
+    /// Handles an `entity_grid/get_cell` request
+    pub fn process_get_cell_request(In(params): In<Option<Value>>, world: &World) -> BrpResult {
+        let params: BrpGetCellParams = parse(params)?;
+        to_value(params.run(world)?)
+    }
+
+    /// Handles an `entity_grid/place` request
+    pub fn process_place_request(In(params): In<Option<Value>>, world: &mut World) -> BrpResult {
+        let params: BrpPlaceParams = parse(params)?;
+        params.run(world)?;
+        Ok(Value::Null)
+    }
+
+    /// Handles an `entity_grid/neighbors` request
+    pub fn process_neighbors_request(In(params): In<Option<Value>>, world: &World) -> BrpResult {
+        let params: BrpNeighborsParams = parse(params)?;
+        to_value(params.run(world)?)
+    }
+
+    fn parse<T: DeserializeOwned>(params: Option<Value>) -> Result<T, BrpError> {
+        let params = params.ok_or_else(|| BrpError {
+            code: error_codes::INVALID_PARAMS,
+            message: "Params not provided".to_string(),
+            data: None,
+        })?;
+        serde_json::from_value(params).map_err(|error| BrpError {
+            code: error_codes::INVALID_PARAMS,
+            message: error.to_string(),
+            data: None,
+        })
+    }
+
+    fn to_value(value: impl Serialize) -> BrpResult {
+        serde_json::to_value(value).map_err(|error| BrpError {
+            code: error_codes::INTERNAL_ERROR,
+            message: error.to_string(),
+            data: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_remote_requests() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        let entity = app.world_mut().spawn_empty().id();
+
+        let place: BrpPlaceParams = serde_json::from_value(json!({
+            "entity": entity,
+            "position": { "x": 1, "y": 2 },
+            "rotation": "Left",
+        }))
+        .unwrap();
+        place.run(app.world_mut()).unwrap();
+        app.update();
+
+        let get_cell: BrpGetCellParams =
+            serde_json::from_value(json!({ "position": { "x": 1, "y": 2 } })).unwrap();
+        assert_eq!(
+            get_cell.run(app.world()),
+            Ok(Some(GridEntity::new(entity, Rotation::Left)))
+        );
+
+        let neighbors: BrpNeighborsParams = serde_json::from_value(json!({
+            "position": { "x": 0, "y": 2 },
+            "query": { "radius": { "shape": "Manhattan", "min": 0, "max": 1, "exclude_centre": true } },
+        }))
+        .unwrap();
+        let neighbors = neighbors.run(app.world()).unwrap();
+        assert_eq!(neighbors.len(), 1);
+        assert_eq!(neighbors[0].position, GridPosition::new(1, 2));
+
+        let missing = BrpGetCellParams {
+            grid: Some(entity),
+            layer: GridLayer::DEFAULT,
+            position: GridPosition::new(0, 0),
+        };
+        assert_eq!(
+            missing.run(app.world()),
+            Err(GridRemoteError::MissingGrid(entity))
+        );
+    }
+}
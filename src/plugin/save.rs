@@ -0,0 +1,449 @@
+use std::{fmt, sync::Arc};
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+pub mod prelude {
+    pub use super::{GridPrefab, GridSave, GridSaveError, GridSpawnRegistry, SavedGridEntity};
+}
+
+use super::systems::sync_grid_positions;
+use crate::prelude::*;
+
+/// The key an entity is saved under and respawned from
+///
+/// Only entities with this component are written to a `GridSave`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct GridPrefab(pub String);
+
+impl GridPrefab {
+    /// Create a prefab key
+    pub fn new(key: impl Into<String>) -> Self {
+        Self(key.into())
+    }
+}
+
+/// A spawn function, adding the components of a prefab to a freshly spawned entity
+pub type GridSpawnFn = dyn Fn(&mut EntityWorldMut) + Send + Sync;
+
+/// The spawn functions used to respawn saved entities, keyed by prefab
+#[derive(Default, Clone, Resource)]
+pub struct GridSpawnRegistry {
+    spawners: HashMap<String, Arc<GridSpawnFn>>,
+}
+
+impl GridSpawnRegistry {
+    /// Register the spawn function of a prefab, replacing any previous one
+    pub fn register(
+        &mut self,
+        key: impl Into<String>,
+        spawn: impl Fn(&mut EntityWorldMut) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.spawners.insert(key.into(), Arc::new(spawn));
+        self
+    }
+
+    /// Get the spawn function of a prefab
+    pub fn get(&self, key: &str) -> Option<&GridSpawnFn> {
+        self.spawners.get(key).map(Arc::as_ref)
+    }
+
+    /// Whether a prefab has a spawn function
+    pub fn contains(&self, key: &str) -> bool {
+        self.spawners.contains_key(key)
+    }
+}
+
+impl fmt::Debug for GridSpawnRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GridSpawnRegistry")
+            .field("prefabs", &self.spawners.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// An entity written to a save
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedGridEntity {
+    /// The prefab the entity is respawned from
+    pub prefab: String,
+    /// The cell of the entity
+    pub position: GridPosition,
+    /// The rotation of the entity
+    pub rotation: Rotation,
+    /// The layer of the entity
+    #[serde(default)]
+    pub layer: GridLayer,
+}
+
+/// The layout of a grid, as written to RON or JSON save files
+///
+/// Entities are ordered by layer and cell, with stacked entities bottom first. Loading places
+/// them one stack level at a time, so the same stacks are rebuilt in a grid using the `Stack`
+/// policy.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GridSave {
+    /// The saved entities
+    pub entities: Vec<SavedGridEntity>,
+}
+
+/// An error while saving or loading a grid
+#[derive(Debug)]
+pub enum GridSaveError {
+    /// The grid entity has no `EntityGridState`
+    MissingGrid(Entity),
+    /// A saved entity uses a prefab without a spawn function
+    UnknownPrefab(String),
+    /// The save stacks entities in a cell but the grid doesn't use the `Stack` policy
+    StackedCells(OccupancyPolicy),
+    /// Writing RON failed
+    Ron(ron::Error),
+    /// Reading RON failed
+    RonParse(ron::error::SpannedError),
+    /// Reading or writing JSON failed
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for GridSaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingGrid(grid) => write!(f, "entity {grid} has no grid state"),
+            Self::UnknownPrefab(prefab) => write!(f, "no spawn function for prefab `{prefab}`"),
+            Self::StackedCells(policy) => {
+                write!(f, "the save stacks entities but the grid uses {policy:?}")
+            }
+            Self::Ron(error) => write!(f, "failed to write RON: {error}"),
+            Self::RonParse(error) => write!(f, "failed to read RON: {error}"),
+            Self::Json(error) => write!(f, "failed to read or write JSON: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for GridSaveError {}
+
+impl GridSave {
+    /// Capture the entities of a grid that have a `GridPrefab`
+    ///
+    /// `None` captures the default grid
+    pub fn capture(world: &World, grid: Option<Entity>) -> Result<Self, GridSaveError> {
+        let state = match grid {
+            Some(grid) => world
+                .get::<EntityGridState>(grid)
+                .ok_or(GridSaveError::MissingGrid(grid))?,
+            None => world.resource::<EntityGridState>(),
+        };
+
+        let mut entities = state
+            .tracked
+            .iter()
+            .filter_map(|(&entity, &(layer, position))| {
+                let prefab = world.get::<GridPrefab>(entity)?;
+                let entry = state.entry(entity)?;
+                // The occupant is above every stacked entity
+                let depth = state
+                    .stacks
+                    .get(&(layer, position))
+                    .and_then(|stack| stack.iter().position(|below| below.entity == entity))
+                    .unwrap_or(usize::MAX);
+                let saved = SavedGridEntity {
+                    prefab: prefab.0.clone(),
+                    position,
+                    rotation: entry.rotation,
+                    layer,
+                };
+                Some(((layer, position.y, position.x, depth), saved))
+            })
+            .collect::<Vec<_>>();
+        entities.sort_by_key(|(key, _)| *key);
+
+        Ok(Self {
+            entities: entities.into_iter().map(|(_, saved)| saved).collect(),
+        })
+    }
+
+    /// Spawn the saved entities into a grid through the `GridSpawnRegistry`
+    ///
+    /// The entities are placed on the next update. Existing entities are kept, so clear the grid
+    /// first to replace its layout. Nothing is spawned if a prefab has no spawn function.
+    ///
+    /// Stacked entities are spawned and placed one level at a time, bottom first, so the stacks
+    /// are rebuilt in save order. Saves with stacks are rejected unless the grid uses the
+    /// `Stack` policy, which would otherwise resolve them by despawning or moving entities.
+    pub fn load(
+        &self,
+        world: &mut World,
+        grid: Option<Entity>,
+    ) -> Result<Vec<Entity>, GridSaveError> {
+        let policy = match grid {
+            Some(grid) => world
+                .get::<EntityGridState>(grid)
+                .ok_or(GridSaveError::MissingGrid(grid))?,
+            None => world.resource::<EntityGridState>(),
+        }
+        .settings
+        .occupancy;
+        let registry = world.resource::<GridSpawnRegistry>();
+        if let Some(unknown) = self
+            .entities
+            .iter()
+            .find(|saved| !registry.contains(&saved.prefab))
+        {
+            return Err(GridSaveError::UnknownPrefab(unknown.prefab.clone()));
+        }
+
+        // The depth of each entity in the stack of its cell, 0 at the bottom
+        let mut counts = HashMap::<(GridLayer, GridPosition), usize>::default();
+        let depths = self
+            .entities
+            .iter()
+            .map(|saved| {
+                let count = counts.entry((saved.layer, saved.position)).or_default();
+                *count += 1;
+                *count - 1
+            })
+            .collect::<Vec<_>>();
+        let levels = depths.iter().max().map_or(0, |depth| depth + 1);
+        if levels > 1 && policy != OccupancyPolicy::Stack {
+            return Err(GridSaveError::StackedCells(policy));
+        }
+
+        let mut entities = vec![Entity::PLACEHOLDER; self.entities.len()];
+        for level in 0..levels {
+            world.resource_scope(|world, registry: Mut<GridSpawnRegistry>| {
+                for (index, saved) in self.entities.iter().enumerate() {
+                    if depths[index] != level {
+                        continue;
+                    }
+                    let mut entity = world.spawn((
+                        GridPrefab(saved.prefab.clone()),
+                        Transform::default(),
+                        saved.position,
+                        GridRotation(saved.rotation),
+                        saved.layer,
+                    ));
+                    if let Some(grid) = grid {
+                        entity.insert(InGrid(grid));
+                    }
+                    if let Some(spawn) = registry.get(&saved.prefab) {
+                        spawn(&mut entity);
+                    }
+                    entities[index] = entity.id();
+                }
+            });
+            // Place this level before spawning the one above it
+            if level + 1 < levels
+                && let Err(error) = world.run_system_once(sync_grid_positions)
+            {
+                warn!("Failed to place the stacked entities of a save: {error}");
+            }
+        }
+        Ok(entities)
+    }
+
+    /// Write the save as pretty RON
+    pub fn to_ron(&self) -> Result<String, GridSaveError> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(GridSaveError::Ron)
+    }
+
+    /// Read a save from RON
+    pub fn from_ron(ron: &str) -> Result<Self, GridSaveError> {
+        ron::from_str(ron).map_err(GridSaveError::RonParse)
+    }
+
+    /// Write the save as pretty JSON
+    pub fn to_json(&self) -> Result<String, GridSaveError> {
+        serde_json::to_string_pretty(self).map_err(GridSaveError::Json)
+    }
+
+    /// Read a save from JSON
+    pub fn from_json(json: &str) -> Result<Self, GridSaveError> {
+        serde_json::from_str(json).map_err(GridSaveError::Json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::entity::EntityHashMap, scene::DynamicSceneBuilder};
+
+    use super::*;
+
+    #[derive(Component)]
+    struct Crate;
+
+    fn setup() -> App {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        app.world_mut()
+            .resource_mut::<GridSpawnRegistry>()
+            .register("crate", |entity| {
+                entity.insert(Crate);
+            });
+        app
+    }
+
+    fn spawn_layout(app: &mut App) {
+        app.world_mut()
+            .resource_mut::<EntityGridState>()
+            .settings
+            .occupancy = OccupancyPolicy::Stack;
+        app.world_mut().spawn((
+            Transform::default(),
+            GridPosition::new(0, 0),
+            GridPrefab::new("crate"),
+        ));
+        app.update();
+        app.world_mut().spawn((
+            Transform::default(),
+            GridPosition::new(0, 0),
+            GridRotation(Rotation::Left),
+            GridPrefab::new("crate"),
+        ));
+        app.world_mut().spawn((
+            Transform::default(),
+            GridPosition::new(2, -1),
+            GridLayer(1),
+            GridPrefab::new("crate"),
+        ));
+        // Entities without a prefab are not saved
+        app.world_mut()
+            .spawn((Transform::default(), GridPosition::new(5, 5)));
+        app.update();
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let mut app = setup();
+        spawn_layout(&mut app);
+        let save = GridSave::capture(app.world(), None).unwrap();
+        assert_eq!(save.entities.len(), 3);
+        assert_eq!(save.entities[0].rotation, Rotation::Up);
+        assert_eq!(save.entities[1].rotation, Rotation::Left);
+        assert_eq!(save.entities[2].layer, GridLayer(1));
+
+        assert_eq!(GridSave::from_ron(&save.to_ron().unwrap()).unwrap(), save);
+        assert_eq!(GridSave::from_json(&save.to_json().unwrap()).unwrap(), save);
+
+        let mut loaded = setup();
+        loaded
+            .world_mut()
+            .resource_mut::<EntityGridState>()
+            .settings
+            .occupancy = OccupancyPolicy::Stack;
+        let entities = save.load(loaded.world_mut(), None).unwrap();
+        loaded.update();
+        assert!(
+            entities
+                .iter()
+                .all(|&entity| loaded.world().get::<Crate>(entity).is_some())
+        );
+        assert_eq!(GridSave::capture(loaded.world(), None).unwrap(), save);
+    }
+
+    #[test]
+    fn test_load_stacks() {
+        let mut app = setup();
+        app.world_mut()
+            .resource_mut::<GridSpawnRegistry>()
+            .register("barrel", |entity| {
+                entity.insert((Crate, Name::new("barrel")));
+            });
+        let saved = |prefab: &str, rotation| SavedGridEntity {
+            prefab: prefab.to_string(),
+            position: GridPosition::new(0, 0),
+            rotation,
+            layer: GridLayer::DEFAULT,
+        };
+        // Prefabs of different archetypes, bottom first
+        let save = GridSave {
+            entities: vec![
+                saved("barrel", Rotation::Up),
+                saved("crate", Rotation::Right),
+                saved("barrel", Rotation::Down),
+            ],
+        };
+
+        // The default `Replace` policy would keep one entity only
+        assert!(matches!(
+            save.load(app.world_mut(), None),
+            Err(GridSaveError::StackedCells(OccupancyPolicy::Replace))
+        ));
+
+        app.world_mut()
+            .resource_mut::<EntityGridState>()
+            .settings
+            .occupancy = OccupancyPolicy::Stack;
+        let entities = save.load(app.world_mut(), None).unwrap();
+        app.update();
+        let state = app.world().resource::<EntityGridState>();
+        assert_eq!(
+            state
+                .grid
+                .get(GridPosition::new(0, 0))
+                .map(|entry| entry.entity),
+            Some(entities[2])
+        );
+        assert_eq!(
+            state.stacks[&(GridLayer::DEFAULT, GridPosition::new(0, 0))]
+                .iter()
+                .map(|entry| entry.entity)
+                .collect::<Vec<_>>(),
+            vec![entities[0], entities[1]]
+        );
+        assert_eq!(GridSave::capture(app.world(), None).unwrap(), save);
+    }
+
+    #[test]
+    fn test_load_unknown_prefab() {
+        let mut app = setup();
+        let save = GridSave {
+            entities: vec![SavedGridEntity {
+                prefab: "barrel".to_string(),
+                position: GridPosition::new(0, 0),
+                rotation: Rotation::Up,
+                layer: GridLayer::DEFAULT,
+            }],
+        };
+        assert!(matches!(
+            save.load(app.world_mut(), None),
+            Err(GridSaveError::UnknownPrefab(prefab)) if prefab == "barrel"
+        ));
+        assert!(
+            app.world_mut()
+                .query::<&GridPrefab>()
+                .iter(app.world())
+                .next()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_dynamic_scene_round_trip() {
+        let mut app = setup();
+        app.register_type::<Transform>();
+        spawn_layout(&mut app);
+
+        let mut query = app.world_mut().query_filtered::<Entity, With<GridPrefab>>();
+        let entities = query.iter(app.world()).collect::<Vec<_>>();
+        let scene = DynamicSceneBuilder::from_world(app.world())
+            .extract_entities(entities.into_iter())
+            .build();
+
+        let mut loaded = setup();
+        loaded.register_type::<Transform>();
+        loaded
+            .world_mut()
+            .resource_mut::<EntityGridState>()
+            .settings
+            .occupancy = OccupancyPolicy::Stack;
+        scene
+            .write_to_world(loaded.world_mut(), &mut EntityHashMap::default())
+            .unwrap();
+        loaded.update();
+
+        let state = loaded.world().resource::<EntityGridState>();
+        assert_eq!(state.tracked.len(), 3);
+        assert!(state.get(GridLayer(1), GridPosition::new(2, -1)).is_some());
+    }
+}
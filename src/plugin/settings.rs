@@ -1,12 +1,449 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{prelude::*, utils::HashMap};
+
 pub mod prelude {
-    pub use super::EntityGridSettings;
+    pub use super::{CellAnchor, EntityGridSettings, GridLattice, GridPlane, OccupancyPolicy};
 }
 
+use crate::prelude::*;
+
 /// The settings for the grid
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Reflect)]
+#[reflect(Default)]
 pub struct EntityGridSettings {
     /// The size of the cell in the grid
     pub cell_size: f32,
     /// the up offset of the grid
     pub up_offset: f32,
+    /// The up offset of each layer, layers without one use `up_offset`
+    pub layer_offsets: HashMap<GridLayer, f32>,
+    /// The height of a level of `VoxelPosition`s
+    pub level_height: f32,
+    /// How to resolve an entity being placed on an occupied cell
+    pub occupancy: OccupancyPolicy,
+    /// The world plane the grid is laid out on
+    pub plane: GridPlane,
+    /// The shape of the cells, square or hexagonal
+    pub lattice: GridLattice,
+}
+
+impl Default for EntityGridSettings {
+    fn default() -> Self {
+        Self {
+            cell_size: 1.0,
+            up_offset: 0.0,
+            layer_offsets: HashMap::default(),
+            level_height: 1.0,
+            occupancy: OccupancyPolicy::default(),
+            plane: GridPlane::default(),
+            lattice: GridLattice::default(),
+        }
+    }
+}
+
+impl EntityGridSettings {
+    /// Get the world position of a cell, relative to the origin of the grid
+    ///
+    /// Entities are placed at the centre of their cell
+    pub fn cell_to_world(&self, position: GridPosition, anchor: CellAnchor) -> Vec3 {
+        self.layer_cell_to_world(position, GridLayer::DEFAULT, anchor)
+    }
+
+    /// Get the world position of a cell on a layer, relative to the origin of the grid
+    pub fn layer_cell_to_world(
+        &self,
+        position: GridPosition,
+        layer: GridLayer,
+        anchor: CellAnchor,
+    ) -> Vec3 {
+        let cell = match self.lattice {
+            GridLattice::Square => {
+                let offset = match anchor {
+                    CellAnchor::Centre => 0.0,
+                    CellAnchor::Corner => -0.5,
+                };
+                Vec2::new(position.x as f32 + offset, position.y as f32 + offset)
+            }
+            GridLattice::Hex(orientation) => {
+                let centre = orientation.to_plane(position.into());
+                match anchor {
+                    CellAnchor::Centre => centre,
+                    CellAnchor::Corner => centre + Vec2::from_angle(orientation.corner_angle()),
+                }
+            }
+        } * self.cell_size;
+        self.plane
+            .to_world(cell.x, cell.y, self.layer_offset(layer))
+    }
+
+    /// Get the world position of a voxel, relative to the origin of the grid
+    ///
+    /// Levels are stacked `level_height` apart, starting at the up offset of the default layer
+    pub fn voxel_to_world(&self, position: VoxelPosition, anchor: CellAnchor) -> Vec3 {
+        let (_, _, up_axis) = self.plane.axes();
+        let level = match anchor {
+            CellAnchor::Centre => position.z as f32,
+            CellAnchor::Corner => position.z as f32 - 0.5,
+        };
+        self.cell_to_world(position.planar(), anchor) + up_axis * level * self.level_height
+    }
+
+    /// Get the voxel containing a world position, relative to the origin of the grid
+    ///
+    /// Levels start at the up offset of the default layer, like in `voxel_to_world`
+    pub fn world_to_voxel(&self, world: Vec3) -> VoxelPosition {
+        let up = self.plane.to_grid(world).z - self.layer_offset(GridLayer::DEFAULT);
+        let level = (up / self.level_height).round() as i32;
+        VoxelPosition::from_planar(self.world_to_cell(world), level)
+    }
+
+    /// Get the world position of the centre of a footprint, relative to the origin of the grid
+    ///
+    /// This is the centre of the box around the centres of the covered cells
+    pub fn footprint_to_world(
+        &self,
+        anchor: GridPosition,
+        footprint: &GridFootprint,
+        rotation: Rotation,
+        layer: GridLayer,
+    ) -> Vec3 {
+        let (min, max) = footprint
+            .cells_at(anchor, rotation)
+            .map(|cell| self.layer_cell_to_world(cell, layer, CellAnchor::Centre))
+            .fold((Vec3::MAX, Vec3::MIN), |(min, max), centre| {
+                (min.min(centre), max.max(centre))
+            });
+        (min + max) / 2.0
+    }
+
+    /// Get the up offset of a layer
+    pub fn layer_offset(&self, layer: GridLayer) -> f32 {
+        self.layer_offsets
+            .get(&layer)
+            .copied()
+            .unwrap_or(self.up_offset)
+    }
+
+    /// Get the cell containing a world position, relative to the origin of the grid
+    ///
+    /// The up component of the position is ignored
+    pub fn world_to_cell(&self, world: Vec3) -> GridPosition {
+        let grid = self.plane.to_grid(world) / self.cell_size;
+        match self.lattice {
+            // Cells are centred on their position, so shift by half a cell before flooring
+            GridLattice::Square => {
+                GridPosition::new((grid.x + 0.5).floor() as i32, (grid.y + 0.5).floor() as i32)
+            }
+            GridLattice::Hex(orientation) => orientation.from_plane(grid.truncate()).into(),
+        }
+    }
+
+    /// Get the cell containing a 2D world position, relative to the origin of the grid
+    pub fn world_2d_to_cell(&self, world: Vec2) -> GridPosition {
+        self.world_to_cell(world.extend(0.0))
+    }
+
+    /// Get the cell a ray hits, relative to the origin of the grid
+    ///
+    /// The ray is intersected with the plane of the grid at the up offset
+    pub fn ray_to_cell(&self, ray: Ray3d) -> Option<GridPosition> {
+        let (x_axis, y_axis, _) = self.plane.axes();
+        let plane_origin = self.plane.to_world(0.0, 0.0, self.up_offset);
+        let normal = Dir3::new(x_axis.cross(y_axis)).ok()?;
+        let distance = ray.intersect_plane(plane_origin, InfinitePlane3d { normal })?;
+        Some(self.world_to_cell(ray.get_point(distance)))
+    }
+
+    /// Get the rotation of an entity, relative to the origin of the grid
+    pub fn rotation(&self, rotation: Rotation) -> Quat {
+        self.plane.rotation(rotation)
+    }
+
+    /// Get the rotation of an entity facing a hex direction, relative to the origin of the grid
+    ///
+    /// Square grids lay the directions out as a pointy hex grid would.
+    pub fn hex_rotation(&self, direction: HexDirection) -> Quat {
+        let orientation = match self.lattice {
+            GridLattice::Square => HexOrientation::Pointy,
+            GridLattice::Hex(orientation) => orientation,
+        };
+        // Entities face grid y when unrotated and turn towards grid x
+        self.plane
+            .turn(FRAC_PI_2 - orientation.direction_angle(direction))
+    }
+}
+
+/// The shape of the cells of a grid
+///
+/// Hex grids use axial coordinates, a `GridPosition` `(x, y)` is the `HexPosition` `(q, r)`.
+/// The cell size of a hex grid is the distance from the centre of a hexagon to its corners.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect)]
+pub enum GridLattice {
+    /// Square cells
+    #[default]
+    Square,
+    /// Hexagonal cells
+    Hex(HexOrientation),
+}
+
+/// How to resolve an entity being placed on a cell that is already occupied
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect)]
+pub enum OccupancyPolicy {
+    /// Despawn the previous occupant
+    #[default]
+    Replace,
+    /// Keep the previous occupant and take the position away from the incoming entity
+    ///
+    /// A newly placed entity loses its `GridPosition`, a moved entity is sent back to its previous cell
+    Reject,
+    /// Put the incoming entity on top of the previous occupant
+    ///
+    /// The previous occupant is restored once the incoming entity leaves the cell
+    Stack,
+    /// Move the previous occupant to the cell the incoming entity came from
+    ///
+    /// Newly placed entities have no previous cell, so the occupant is nudged instead
+    Swap,
+    /// Move the incoming entity to the nearest free cell
+    Nudge,
+}
+
+/// The point of a cell to convert to the world
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Reflect)]
+pub enum CellAnchor {
+    /// The centre of the cell, where entities are placed
+    #[default]
+    Centre,
+    /// The corner of the cell with the lowest grid coordinates
+    Corner,
+}
+
+/// The world plane a grid is laid out on
+#[derive(Debug, Default, Copy, Clone, PartialEq, Reflect)]
+pub enum GridPlane {
+    /// Grid x along X, grid y along Z and up along Y, for 3D grids
+    #[default]
+    XZ,
+    /// Grid x along X, grid y along Y and up along Z, for 2D grids
+    XY,
+    /// Custom world axes for grid x, grid y and up
+    Custom {
+        /// The world direction of grid x
+        x: Vec3,
+        /// The world direction of grid y
+        y: Vec3,
+        /// The world direction of the up offset
+        up: Vec3,
+    },
+}
+
+impl GridPlane {
+    /// Get the world axes of grid x, grid y and up
+    pub fn axes(&self) -> (Vec3, Vec3, Vec3) {
+        match *self {
+            Self::XZ => (Vec3::X, Vec3::Z, Vec3::Y),
+            Self::XY => (Vec3::X, Vec3::Y, Vec3::Z),
+            Self::Custom { x, y, up } => (x, y, up),
+        }
+    }
+
+    /// Map a point in grid space to the world
+    pub fn to_world(&self, x: f32, y: f32, up: f32) -> Vec3 {
+        let (x_axis, y_axis, up_axis) = self.axes();
+        x_axis * x + y_axis * y + up_axis * up
+    }
+
+    /// Map a world point to grid space, as grid x, grid y and up
+    pub fn to_grid(&self, world: Vec3) -> Vec3 {
+        let (x_axis, y_axis, up_axis) = self.axes();
+        Mat3::from_cols(x_axis, y_axis, up_axis).inverse() * world
+    }
+
+    /// Get the world rotation of a grid rotation
+    ///
+    /// The rotation turns grid y (`Rotation::Up`) towards grid x (`Rotation::Right`),
+    /// around the Y axis for `XZ` and clockwise around the Z axis for `XY`.
+    pub fn rotation(&self, rotation: Rotation) -> Quat {
+        self.turn(rotation.to_angle())
+    }
+
+    /// Get the world rotation turning grid y towards grid x by an angle
+    pub fn turn(&self, angle: f32) -> Quat {
+        let (x_axis, y_axis, _) = self.axes();
+        Quat::from_axis_angle(y_axis.cross(x_axis).normalize(), angle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_3;
+
+    use super::*;
+
+    #[test]
+    fn test_plane_translation() {
+        let mut settings = EntityGridSettings {
+            cell_size: 2.0,
+            up_offset: 0.5,
+            ..default()
+        };
+        assert_eq!(
+            settings.cell_to_world(GridPosition::new(1, 3), CellAnchor::Centre),
+            Vec3::new(2.0, 0.5, 6.0)
+        );
+
+        settings.plane = GridPlane::XY;
+        assert_eq!(
+            settings.cell_to_world(GridPosition::new(1, 3), CellAnchor::Centre),
+            Vec3::new(2.0, 6.0, 0.5)
+        );
+    }
+
+    #[test]
+    fn test_plane_rotation() {
+        assert!(
+            GridPlane::XZ
+                .rotation(Rotation::Right)
+                .abs_diff_eq(Quat::from_rotation_y(Rotation::Right.to_angle()), 1e-6)
+        );
+        // Rotating right turns grid y towards grid x on every plane
+        for plane in [GridPlane::XZ, GridPlane::XY] {
+            let (x_axis, y_axis, _) = plane.axes();
+            assert!((plane.rotation(Rotation::Right) * y_axis).abs_diff_eq(x_axis, 1e-6));
+        }
+    }
+
+    #[test]
+    fn test_world_to_cell() {
+        let settings = EntityGridSettings {
+            cell_size: 2.0,
+            ..default()
+        };
+        assert_eq!(
+            settings.world_to_cell(Vec3::new(0.9, 5.0, -0.9)),
+            GridPosition::new(0, 0)
+        );
+        assert_eq!(
+            settings.world_to_cell(Vec3::new(-1.1, 0.0, 3.1)),
+            GridPosition::new(-1, 2)
+        );
+        // Corners map back to the cell they belong to
+        let corner = settings.cell_to_world(GridPosition::new(-3, 4), CellAnchor::Corner);
+        assert_eq!(
+            settings.world_to_cell(corner + Vec3::new(0.01, 0.0, 0.01)),
+            GridPosition::new(-3, 4)
+        );
+
+        let settings = EntityGridSettings {
+            plane: GridPlane::XY,
+            ..settings
+        };
+        assert_eq!(
+            settings.world_2d_to_cell(Vec2::new(-2.5, 4.5)),
+            GridPosition::new(-1, 2)
+        );
+    }
+
+    #[test]
+    fn test_ray_to_cell() {
+        let settings = EntityGridSettings::default();
+        let ray = Ray3d::new(Vec3::new(3.0, 10.0, -2.0), Dir3::NEG_Y);
+        assert_eq!(settings.ray_to_cell(ray), Some(GridPosition::new(3, -2)));
+
+        let away = Ray3d::new(Vec3::new(3.0, 10.0, -2.0), Dir3::Y);
+        assert_eq!(settings.ray_to_cell(away), None);
+    }
+
+    #[test]
+    fn test_hex_lattice() {
+        let settings = EntityGridSettings {
+            cell_size: 2.0,
+            plane: GridPlane::XY,
+            lattice: GridLattice::Hex(HexOrientation::Pointy),
+            ..default()
+        };
+        let hex = GridPosition::new(1, -2);
+        let centre = settings.cell_to_world(hex, CellAnchor::Centre);
+        assert!(centre.abs_diff_eq(Vec3::new(0.0, -6.0, 0.0), 1e-5));
+        assert_eq!(settings.world_to_cell(centre), hex);
+        // The corner is shared with other hexes, step back towards the centre
+        let corner = settings.cell_to_world(hex, CellAnchor::Corner);
+        assert_eq!(settings.world_to_cell(corner.lerp(centre, 0.05)), hex);
+
+        // Facing east turns grid y onto grid x
+        let rotation = settings.hex_rotation(HexDirection::East);
+        assert!((rotation * Vec3::Y).abs_diff_eq(Vec3::X, 1e-6));
+        let rotation = settings.hex_rotation(HexDirection::NorthWest);
+        let expected = Vec2::from_angle(2.0 * FRAC_PI_3).extend(0.0);
+        assert!((rotation * Vec3::Y).abs_diff_eq(expected, 1e-6));
+    }
+
+    #[test]
+    fn test_voxel_to_world() {
+        let settings = EntityGridSettings {
+            up_offset: 0.5,
+            level_height: 3.0,
+            ..default()
+        };
+        let position = VoxelPosition::new(1, 2, 2);
+        let world = settings.voxel_to_world(position, CellAnchor::Centre);
+        assert_eq!(world, Vec3::new(1.0, 6.5, 2.0));
+        assert_eq!(settings.world_to_voxel(world + Vec3::splat(0.4)), position);
+        assert_eq!(
+            settings.voxel_to_world(position, CellAnchor::Corner),
+            Vec3::new(0.5, 5.0, 1.5)
+        );
+
+        // The default layer can have its own offset
+        let settings = EntityGridSettings {
+            layer_offsets: HashMap::from_iter([(GridLayer::DEFAULT, 4.0)]),
+            ..settings
+        };
+        for position in [position, VoxelPosition::new(-3, 0, -1)] {
+            let world = settings.voxel_to_world(position, CellAnchor::Centre);
+            assert_eq!(settings.world_to_voxel(world), position);
+        }
+    }
+
+    #[test]
+    fn test_footprint_to_world() {
+        let settings = EntityGridSettings::default();
+        let footprint = GridFootprint::rectangle(2, 3);
+        assert_eq!(
+            settings.footprint_to_world(
+                GridPosition::new(0, 0),
+                &footprint,
+                Rotation::Up,
+                GridLayer::DEFAULT
+            ),
+            Vec3::new(0.5, 0.0, 1.0)
+        );
+        assert_eq!(
+            settings.footprint_to_world(
+                GridPosition::new(0, 0),
+                &footprint,
+                Rotation::Right,
+                GridLayer::DEFAULT
+            ),
+            Vec3::new(1.0, 0.0, -0.5)
+        );
+    }
+
+    #[test]
+    fn test_layer_offset() {
+        let settings = EntityGridSettings {
+            up_offset: 0.5,
+            layer_offsets: HashMap::from_iter([(GridLayer(1), 2.0)]),
+            ..default()
+        };
+        assert_eq!(
+            settings.layer_cell_to_world(GridPosition::new(1, 1), GridLayer(1), CellAnchor::Centre),
+            Vec3::new(1.0, 2.0, 1.0)
+        );
+        assert_eq!(
+            settings.layer_cell_to_world(GridPosition::new(1, 1), GridLayer(2), CellAnchor::Centre),
+            Vec3::new(1.0, 0.5, 1.0)
+        );
+    }
 }
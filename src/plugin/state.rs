@@ -1,4 +1,7 @@
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
 
 pub mod prelude {
     pub use super::EntityGridState;
@@ -7,12 +10,374 @@ pub mod prelude {
 use crate::prelude::*;
 
 /// The state of the grid
-#[derive(Debug, Clone, Resource)]
+///
+/// The default grid is stored as a resource, additional grids are stored as components on grid entities.
+/// Every layer is a chunked `Grid`, other storages such as `BoundedGrid` are used outside of the plugin.
+#[derive(Debug, Clone, Resource, Component, Reflect)]
+#[reflect(Resource, Component)]
 pub struct EntityGridState {
-    /// The grid
+    /// The grid of the default layer
     pub grid: Grid,
+    /// The grids of the other layers
+    pub layers: HashMap<GridLayer, Grid>,
     /// The settings for the grid
     pub settings: EntityGridSettings,
-    /// Spawn Rotation
+    /// Spawn Rotation, used for entities placed without a `GridRotation`
     pub spawn_rotation: Rotation,
+    /// The last layer and position each tracked entity was registered at
+    pub tracked: HashMap<Entity, (GridLayer, GridPosition)>,
+    /// The entities buried beneath the occupant of a cell, bottom first
+    pub stacks: HashMap<(GridLayer, GridPosition), Vec<GridEntity>>,
+    /// The cells covered by each entity with a multi-cell `GridFootprint`, anchor included
+    pub footprints: HashMap<Entity, Vec<GridPosition>>,
+    /// The chunks streamed in around the `GridStreamAnchor`s of the grid
+    pub loaded_chunks: HashSet<ChunkPosition>,
+    /// The entities placed with a `VoxelPosition`
+    pub voxels: VoxelGrid,
+    /// The last voxel each entity with a `VoxelPosition` was registered at
+    pub voxel_tracked: HashMap<Entity, VoxelPosition>,
+    /// What the teams with a `GridViewer` in the grid see
+    pub fog: GridFog,
+}
+
+impl EntityGridState {
+    /// Create an empty grid with the given settings
+    pub fn new(settings: EntityGridSettings) -> Self {
+        Self {
+            grid: Grid::default(),
+            layers: HashMap::default(),
+            settings,
+            spawn_rotation: Rotation::default(),
+            tracked: HashMap::default(),
+            stacks: HashMap::default(),
+            footprints: HashMap::default(),
+            loaded_chunks: HashSet::default(),
+            voxels: VoxelGrid::default(),
+            voxel_tracked: HashMap::default(),
+            fog: GridFog::default(),
+        }
+    }
+
+    /// Get the grid of a layer, `None` if nothing was ever placed on it
+    pub fn layer(&self, layer: GridLayer) -> Option<&Grid> {
+        match layer {
+            GridLayer::DEFAULT => Some(&self.grid),
+            _ => self.layers.get(&layer),
+        }
+    }
+
+    /// Get the mutable grid of a layer, creating it if needed
+    pub fn layer_mut(&mut self, layer: GridLayer) -> &mut Grid {
+        match layer {
+            GridLayer::DEFAULT => &mut self.grid,
+            _ => self.layers.entry(layer).or_default(),
+        }
+    }
+
+    /// Iterate over the grids of every layer, the default layer first
+    pub fn layers(&self) -> impl Iterator<Item = (GridLayer, &Grid)> {
+        std::iter::once((GridLayer::DEFAULT, &self.grid))
+            .chain(self.layers.iter().map(|(layer, grid)| (*layer, grid)))
+    }
+
+    /// Get the chunks with an occupied cell on any layer
+    pub fn occupied_chunks(&self) -> HashSet<ChunkPosition> {
+        self.layers()
+            .flat_map(|(_, grid)| grid.chunks().map(GridChunk::position))
+            .collect()
+    }
+
+    /// Get every entry of a chunk on every layer, with stacked entries bottom first
+    pub fn chunk_entries(
+        &self,
+        chunk: ChunkPosition,
+    ) -> Vec<(GridLayer, GridPosition, GridEntity)> {
+        let mut layers = self.layers().collect::<Vec<_>>();
+        layers.sort_by_key(|(layer, _)| *layer);
+
+        let mut entries = Vec::new();
+        for (layer, grid) in layers {
+            let Some(cells) = grid.chunk(chunk) else {
+                continue;
+            };
+            for (position, entry) in cells.iter() {
+                // Entities covering several cells belong to the chunk of their anchor
+                if self.footprints.contains_key(&entry.entity)
+                    && self.tracked.get(&entry.entity) != Some(&(layer, position))
+                {
+                    continue;
+                }
+                if let Some(stack) = self.stacks.get(&(layer, position)) {
+                    entries.extend(stack.iter().map(|below| (layer, position, *below)));
+                }
+                entries.push((layer, position, *entry));
+            }
+        }
+        entries
+    }
+
+    /// Get the occupant of a cell on a layer
+    pub fn get(&self, layer: GridLayer, position: GridPosition) -> Option<GridEntity> {
+        self.layer(layer)?.get(position)
+    }
+
+    /// Get the cardinal neighbors of a position on a layer
+    pub fn get_cardinal_neighbors(
+        &self,
+        position: GridPosition,
+        layer: GridLayer,
+    ) -> CardinalNeighbors {
+        self.layer(layer)
+            .map(|grid| grid.get_cardinal_neighbors(position))
+            .unwrap_or_default()
+    }
+
+    /// Get the ordinal neighbors of a position on a layer
+    pub fn get_ordinal_neighbors(
+        &self,
+        position: GridPosition,
+        layer: GridLayer,
+    ) -> OrdinalNeighbors {
+        self.layer(layer)
+            .map(|grid| grid.get_ordinal_neighbors(position))
+            .unwrap_or_default()
+    }
+
+    /// Get the six neighbors of a position on a hex layer
+    pub fn get_hex_neighbors(&self, position: GridPosition, layer: GridLayer) -> HexNeighbors {
+        self.layer(layer)
+            .map(|grid| grid.get_hex_neighbors(position))
+            .unwrap_or_default()
+    }
+
+    /// Get the square of neighbors with the given radius on a layer
+    pub fn get_square_radius_neighbors(
+        &self,
+        position: GridPosition,
+        radius: i32,
+        layer: GridLayer,
+    ) -> RadiusNeighbors {
+        self.get_radius_neighbors(position, RadiusQuery::chebyshev(radius), layer)
+    }
+
+    /// Get the rounded neighbors with the given radius on a layer
+    pub fn get_rounded_radius_neighbors(
+        &self,
+        position: GridPosition,
+        radius: i32,
+        layer: GridLayer,
+    ) -> RadiusNeighbors {
+        self.get_radius_neighbors(position, RadiusQuery::circle(radius), layer)
+    }
+
+    /// Get the occupied cells of a radius query around a position on a layer
+    ///
+    /// Entities covering several cells with a `GridFootprint` are returned once, at the first
+    /// cell found.
+    pub fn get_radius_neighbors(
+        &self,
+        position: GridPosition,
+        query: RadiusQuery,
+        layer: GridLayer,
+    ) -> RadiusNeighbors {
+        let Some(grid) = self.layer(layer) else {
+            return RadiusNeighbors::default();
+        };
+        let mut seen = HashSet::new();
+        RadiusNeighbors {
+            neighbors: grid
+                .iter_radius_neighbors(position, query)
+                .filter(|neighbor| {
+                    !self.footprints.contains_key(&neighbor.entry.entity)
+                        || seen.insert(neighbor.entry.entity)
+                })
+                .collect(),
+        }
+    }
+
+    /// Register an entity in every cell of a footprint, replacing its previous footprint
+    pub fn insert_footprint(
+        &mut self,
+        entity: Entity,
+        layer: GridLayer,
+        anchor: GridPosition,
+        footprint: &GridFootprint,
+        rotation: Rotation,
+    ) {
+        self.clear_footprint(entity, layer);
+        let cells = footprint.cells_at(anchor, rotation).collect::<Vec<_>>();
+        let grid = self.layer_mut(layer);
+        for cell in &cells {
+            grid.insert(*cell, entity, rotation);
+        }
+        self.footprints.insert(entity, cells);
+    }
+
+    /// Remove an entity from the cells of its footprint
+    fn clear_footprint(&mut self, entity: Entity, layer: GridLayer) {
+        let Some(cells) = self.footprints.remove(&entity) else {
+            return;
+        };
+        let grid = self.layer_mut(layer);
+        for cell in cells {
+            if grid.get(cell).is_some_and(|entry| entry.entity == entity) {
+                grid.remove(cell);
+            }
+        }
+    }
+
+    /// Remove an entity from the given cell
+    ///
+    /// If the entity is the occupant, the top of the cell's stack takes its place.
+    /// Returns the removed entry, or `None` if the entity was not in the cell.
+    pub fn vacate(
+        &mut self,
+        entity: Entity,
+        layer: GridLayer,
+        position: GridPosition,
+    ) -> Option<GridEntity> {
+        // Entities with a footprint are never stacked
+        if self.footprints.contains_key(&entity) {
+            let removed = self
+                .get(layer, position)
+                .filter(|entry| entry.entity == entity);
+            self.clear_footprint(entity, layer);
+            return removed;
+        }
+
+        let key = (layer, position);
+        if self
+            .get(layer, position)
+            .is_some_and(|entry| entry.entity == entity)
+        {
+            let below = self.stacks.get_mut(&key).and_then(Vec::pop);
+            if self.stacks.get(&key).is_some_and(Vec::is_empty) {
+                self.stacks.remove(&key);
+            }
+            let grid = self.layer_mut(layer);
+            let removed = grid.remove(position);
+            if let Some(below) = below {
+                // The whole entry is restored, tags included
+                let _ = grid.storage_mut().insert(position, below);
+            }
+            return removed;
+        }
+
+        let stack = self.stacks.get_mut(&key)?;
+        let index = stack.iter().position(|entry| entry.entity == entity)?;
+        let removed = stack.remove(index);
+        if stack.is_empty() {
+            self.stacks.remove(&key);
+        }
+        Some(removed)
+    }
+
+    /// Stop tracking an entity and remove it from its cell
+    ///
+    /// Returns the cell and entry of the entity, or `None` if it was not in the grid
+    pub fn untrack(&mut self, entity: Entity) -> Option<(GridLayer, GridPosition, GridEntity)> {
+        let (layer, position) = self.tracked.remove(&entity)?;
+        self.vacate(entity, layer, position)
+            .map(|entry| (layer, position, entry))
+    }
+
+    /// Stop tracking a voxel entity and remove it from its voxel
+    ///
+    /// Returns the voxel and entry of the entity, or `None` if it was not in the grid
+    pub fn untrack_voxel(&mut self, entity: Entity) -> Option<(VoxelPosition, GridEntity)> {
+        let position = self.voxel_tracked.remove(&entity)?;
+        if self
+            .voxels
+            .get(position)
+            .is_none_or(|entry| entry.entity != entity)
+        {
+            return None;
+        }
+        self.voxels.remove(position).map(|entry| (position, entry))
+    }
+
+    /// Get the entry of a tracked entity, whether it occupies its cell or is stacked below
+    pub fn entry(&self, entity: Entity) -> Option<GridEntity> {
+        let key = *self.tracked.get(&entity)?;
+        let (layer, position) = key;
+        match self.get(layer, position) {
+            Some(entry) if entry.entity == entity => Some(entry),
+            _ => self
+                .stacks
+                .get(&key)?
+                .iter()
+                .find(|entry| entry.entity == entity)
+                .copied(),
+        }
+    }
+
+    /// Get the mutable entry of a tracked entity, whether it occupies its cell or is stacked below
+    pub fn entry_mut(&mut self, entity: Entity) -> Option<&mut GridEntity> {
+        let key = *self.tracked.get(&entity)?;
+        let (layer, position) = key;
+        let grid = match layer {
+            GridLayer::DEFAULT => &mut self.grid,
+            _ => self.layers.get_mut(&layer)?,
+        };
+        match grid.get_mut(position) {
+            Some(entry) if entry.entity == entity => Some(entry),
+            _ => self
+                .stacks
+                .get_mut(&key)?
+                .iter_mut()
+                .find(|entry| entry.entity == entity),
+        }
+    }
+
+    /// Set the tags of a tracked entity in every cell it covers, returning whether it was found
+    pub fn set_tags(&mut self, entity: Entity, tags: GridTags) -> bool {
+        if let Some(cells) = self.footprints.get(&entity).cloned()
+            && let Some(&(layer, _)) = self.tracked.get(&entity)
+        {
+            let grid = self.layer_mut(layer);
+            for cell in cells {
+                if let Some(entry) = grid.get_mut(cell)
+                    && entry.entity == entity
+                {
+                    entry.tags = tags;
+                }
+            }
+            return true;
+        }
+        match self.entry_mut(entity) {
+            Some(entry) => {
+                entry.tags = tags;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layer_neighbors() {
+        let mut state = EntityGridState::new(EntityGridSettings::default());
+        let floor = GridLayer(1);
+        state
+            .layer_mut(floor)
+            .insert(GridPosition::new(0, 1), Entity::PLACEHOLDER, Rotation::Up);
+        state
+            .grid
+            .insert(GridPosition::new(1, 0), Entity::PLACEHOLDER, Rotation::Up);
+
+        let neighbors = state.get_cardinal_neighbors(GridPosition::new(0, 0), floor);
+        assert!(neighbors.north.is_some());
+        assert!(neighbors.east.is_none());
+
+        let neighbors = state.get_cardinal_neighbors(GridPosition::new(0, 0), GridLayer::DEFAULT);
+        assert!(neighbors.north.is_none());
+        assert!(neighbors.east.is_some());
+
+        let neighbors = state.get_square_radius_neighbors(GridPosition::new(0, 0), 1, GridLayer(2));
+        assert!(neighbors.neighbors.is_empty());
+    }
 }
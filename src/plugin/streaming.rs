@@ -0,0 +1,284 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+pub mod prelude {
+    pub use super::{GridChunkStorage, GridChunkStore, GridStreamAnchor, MemoryChunkStorage};
+}
+
+use crate::prelude::*;
+
+/// Streams the chunks of a grid in and out around this entity
+///
+/// Chunks within `radius` chunks of any anchor of a grid are loaded, the others are unloaded:
+/// their entities with a `GridPrefab` are written to the `GridChunkStore` and despawned, and
+/// respawned through the `GridSpawnRegistry` once the chunk is loaded again.
+/// Entities without a `GridPrefab` are never unloaded. Grids without anchors don't stream.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct GridStreamAnchor {
+    /// The grid to stream, `None` for the default grid
+    pub grid: Option<Entity>,
+    /// The number of chunks to load around the anchor, in every direction
+    pub radius: i32,
+}
+
+/// Where unloaded chunks are kept until they stream back in
+///
+/// Implement this to stream chunks to disk, for instance by writing a `GridSave` per chunk.
+pub trait GridChunkStorage: Send + Sync + 'static {
+    /// Keep the entities of an unloaded chunk, along with any already stored for it
+    ///
+    /// A chunk can be unloaded again before it was loaded, when an entity enters it.
+    fn store(&mut self, grid: Option<Entity>, chunk: ChunkPosition, entities: Vec<SavedGridEntity>);
+
+    /// Take back the entities of a chunk being loaded, `None` if nothing was stored
+    fn take(&mut self, grid: Option<Entity>, chunk: ChunkPosition) -> Option<Vec<SavedGridEntity>>;
+}
+
+/// Keeps unloaded chunks in memory
+#[derive(Debug, Default, Clone)]
+pub struct MemoryChunkStorage {
+    /// The stored entities of each unloaded chunk
+    pub chunks: HashMap<(Option<Entity>, ChunkPosition), Vec<SavedGridEntity>>,
+}
+
+impl GridChunkStorage for MemoryChunkStorage {
+    fn store(
+        &mut self,
+        grid: Option<Entity>,
+        chunk: ChunkPosition,
+        entities: Vec<SavedGridEntity>,
+    ) {
+        self.chunks
+            .entry((grid, chunk))
+            .or_default()
+            .extend(entities);
+    }
+
+    fn take(&mut self, grid: Option<Entity>, chunk: ChunkPosition) -> Option<Vec<SavedGridEntity>> {
+        self.chunks.remove(&(grid, chunk))
+    }
+}
+
+/// The storage of unloaded chunks, in memory by default
+#[derive(Resource)]
+pub struct GridChunkStore(pub Box<dyn GridChunkStorage>);
+
+impl Default for GridChunkStore {
+    fn default() -> Self {
+        Self(Box::new(MemoryChunkStorage::default()))
+    }
+}
+
+/// Load and unload chunks around the `GridStreamAnchor`s
+pub fn stream_grid_chunks(
+    mut commands: Commands,
+    anchors: Query<(&GlobalTransform, &GridStreamAnchor)>,
+    prefabs: Query<&GridPrefab>,
+    mut store: ResMut<GridChunkStore>,
+    mut loaded: EventWriter<ChunkLoaded>,
+    mut unloaded: EventWriter<ChunkUnloaded>,
+    mut grids: Grids,
+) {
+    let mut desired = HashMap::<Option<Entity>, HashSet<ChunkPosition>>::default();
+    for (transform, anchor) in &anchors {
+        let Some(cell) = grids.world_to_cell(anchor.grid, transform.translation()) else {
+            continue;
+        };
+        let centre = ChunkPosition::of(cell);
+        let chunks = desired.entry(anchor.grid).or_default();
+        for x in -anchor.radius..=anchor.radius {
+            for y in -anchor.radius..=anchor.radius {
+                chunks.insert(ChunkPosition::new(centre.x + x, centre.y + y));
+            }
+        }
+    }
+
+    for (grid, desired) in desired {
+        let Some(state) = grids.state_mut(grid) else {
+            continue;
+        };
+
+        let mut outside = state
+            .loaded_chunks
+            .union(&state.occupied_chunks())
+            .filter(|chunk| !desired.contains(*chunk))
+            .copied()
+            .collect::<Vec<_>>();
+        outside.sort();
+        for chunk in outside {
+            let entities = state
+                .chunk_entries(chunk)
+                .into_iter()
+                .filter_map(|(layer, position, entry)| {
+                    let prefab = prefabs.get(entry.entity).ok()?;
+                    commands.entity(entry.entity).despawn_recursive();
+                    Some(SavedGridEntity {
+                        prefab: prefab.0.clone(),
+                        position,
+                        rotation: entry.rotation,
+                        layer,
+                    })
+                })
+                .collect::<Vec<_>>();
+            // Chunks that were never loaded only unload if they hold something to store
+            if !state.loaded_chunks.remove(&chunk) && entities.is_empty() {
+                continue;
+            }
+            let stored = entities.len();
+            if stored > 0 {
+                store.0.store(grid, chunk, entities);
+            }
+            unloaded.send(ChunkUnloaded {
+                grid,
+                chunk,
+                stored,
+            });
+        }
+
+        let mut inside = desired
+            .into_iter()
+            .filter(|chunk| !state.loaded_chunks.contains(chunk))
+            .collect::<Vec<_>>();
+        inside.sort();
+        for chunk in inside {
+            state.loaded_chunks.insert(chunk);
+            let entities = store.0.take(grid, chunk).unwrap_or_default();
+            let respawned = entities.len();
+            if respawned > 0 {
+                commands.queue(move |world: &mut World| {
+                    if let Err(error) = (GridSave { entities }).load(world, grid) {
+                        warn!("Failed to respawn chunk {chunk:?}: {error}");
+                    }
+                });
+            }
+            loaded.send(ChunkLoaded {
+                grid,
+                chunk,
+                respawned,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> App {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        app.world_mut()
+            .resource_mut::<GridSpawnRegistry>()
+            .register("tree", |_| {});
+        app
+    }
+
+    fn events<E: Event + Clone>(app: &App) -> Vec<E> {
+        let events = app.world().resource::<Events<E>>();
+        events.get_cursor().read(events).cloned().collect()
+    }
+
+    #[test]
+    fn test_stream_chunks() {
+        let mut app = setup();
+        let near = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GridPosition::new(0, 0),
+                GridPrefab::new("tree"),
+            ))
+            .id();
+        app.world_mut().spawn((
+            Transform::default(),
+            GridPosition::new(40, 0),
+            GridPrefab::new("tree"),
+        ));
+        // Entities without a prefab stay loaded
+        let rock = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(41, 0)))
+            .id();
+        app.update();
+
+        let anchor = app
+            .world_mut()
+            .spawn((GlobalTransform::default(), GridStreamAnchor {
+                grid: None,
+                radius: 0,
+            }))
+            .id();
+        app.update();
+        assert_eq!(events::<ChunkUnloaded>(&app), vec![ChunkUnloaded {
+            grid: None,
+            chunk: ChunkPosition::new(2, 0),
+            stored: 1,
+        }]);
+        app.update();
+        let state = app.world().resource::<EntityGridState>();
+        assert!(!state.grid.contains(GridPosition::new(40, 0)));
+        assert!(state.grid.contains(GridPosition::new(41, 0)));
+        assert!(app.world().get_entity(rock).is_ok());
+
+        // Move the anchor to the far chunk
+        *app.world_mut().get_mut::<GlobalTransform>(anchor).unwrap() =
+            GlobalTransform::from_translation(Vec3::new(40.0, 0.0, 0.0));
+        app.update();
+        assert!(events::<ChunkLoaded>(&app).contains(&ChunkLoaded {
+            grid: None,
+            chunk: ChunkPosition::new(2, 0),
+            respawned: 1,
+        }));
+        app.update();
+        let state = app.world().resource::<EntityGridState>();
+        assert!(state.grid.contains(GridPosition::new(40, 0)));
+        assert!(!state.grid.contains(GridPosition::new(0, 0)));
+        assert!(app.world().get_entity(near).is_err());
+        assert_eq!(state.loaded_chunks.iter().collect::<Vec<_>>(), vec![
+            &ChunkPosition::new(2, 0)
+        ]);
+    }
+
+    #[test]
+    fn test_unload_stored_chunk() {
+        let mut app = setup();
+        let tree = (Transform::default(), GridPrefab::new("tree"));
+        app.world_mut()
+            .spawn((tree.clone(), GridPosition::new(40, 0)));
+        let anchor = app
+            .world_mut()
+            .spawn((GlobalTransform::default(), GridStreamAnchor {
+                grid: None,
+                radius: 0,
+            }))
+            .id();
+        app.update();
+
+        // An entity entering the stored chunk is unloaded without losing the stored ones
+        app.world_mut()
+            .spawn((tree.clone(), GridPosition::new(41, 1)));
+        app.update();
+        let unload = ChunkUnloaded {
+            grid: None,
+            chunk: ChunkPosition::new(2, 0),
+            stored: 1,
+        };
+        assert_eq!(events::<ChunkUnloaded>(&app), vec![unload.clone(), unload]);
+
+        *app.world_mut().get_mut::<GlobalTransform>(anchor).unwrap() =
+            GlobalTransform::from_translation(Vec3::new(40.0, 0.0, 0.0));
+        app.update();
+        assert!(events::<ChunkLoaded>(&app).contains(&ChunkLoaded {
+            grid: None,
+            chunk: ChunkPosition::new(2, 0),
+            respawned: 2,
+        }));
+        app.update();
+        let state = app.world().resource::<EntityGridState>();
+        assert!(state.grid.contains(GridPosition::new(40, 0)));
+        assert!(state.grid.contains(GridPosition::new(41, 1)));
+    }
+}
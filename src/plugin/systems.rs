@@ -0,0 +1,1358 @@
+use bevy::prelude::*;
+
+use crate::prelude::*;
+
+/// The components written when an entity is placed in the grid
+type PlacementQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut GridPosition,
+        &'static mut Transform,
+        Option<&'static GridRotation>,
+        Option<&'static InGrid>,
+        Option<&'static GridLayer>,
+        Option<&'static HexFacing>,
+        Option<&'static GridFootprint>,
+        Option<&'static GridMotion>,
+    ),
+>;
+
+/// The entities whose cell, layer, grid or footprint changed
+type ChangedQuery<'w, 's> = Query<
+    'w,
+    's,
+    Entity,
+    (
+        With<GridPosition>,
+        Or<(
+            Changed<GridPosition>,
+            Changed<InGrid>,
+            Changed<GridLayer>,
+            Changed<GridFootprint>,
+        )>,
+    ),
+>;
+
+/// The placed entities whose rotation changed
+type RotatedQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut GridRotation,
+        &'static mut Transform,
+        Has<HexFacing>,
+        Option<&'static GridFootprint>,
+    ),
+    (With<GridPosition>, Changed<GridRotation>),
+>;
+
+/// The placed entities whose hex facing changed
+type FacingQuery<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static HexFacing, &'static mut Transform),
+    (With<GridPosition>, Changed<HexFacing>),
+>;
+
+/// Register added or moved entities in their grid
+///
+/// A moved entity is removed from its previous cell and keeps its rotation.
+/// Newly added entities use their `GridRotation`, falling back to the spawn rotation.
+/// Entities placed on an occupied cell are resolved with the `OccupancyPolicy` of the settings.
+/// Entities moving to another grid or layer are removed from the previous one and placed in the new one.
+/// Entities with a multi-cell `GridFootprint` are placed only if every covered cell is free: the
+/// `Replace` policy despawns the occupants, any other policy rejects the placement.
+/// The destination of a moving `GridMotion` is reserved: entering it is rejected whatever the policy.
+/// Placements refused by the `GridPlacementRules` are rejected too.
+pub fn sync_grid_positions(
+    mut commands: Commands,
+    mut queries: ParamSet<(ChangedQuery, PlacementQuery)>,
+    mut conflicts: EventWriter<OccupancyConflict>,
+    mut placed: EventWriter<EntityPlaced>,
+    mut moved: EventWriter<EntityMoved>,
+    mut removed: EventWriter<EntityRemoved>,
+    mut grids: Grids,
+) {
+    let changed_entities: Vec<Entity> = queries.p0().iter().collect();
+    let mut query_common = queries.p1();
+    let rules = grids.placement_rules().clone();
+
+    changed_entities.into_iter().for_each(|incoming_entity| {
+        let Ok((position, _, grid_rotation, in_grid, grid_layer, _, footprint, _)) =
+            query_common.get(incoming_entity)
+        else {
+            return;
+        };
+        let mut target = *position;
+        let footprint = footprint
+            .filter(|footprint| footprint.is_multi_cell())
+            .cloned();
+        let grid_rotation = grid_rotation.copied();
+        let grid = in_grid.map(|InGrid(grid)| *grid);
+        let layer = grid_layer.copied().unwrap_or_default();
+
+        // Leave the previous grid or layer, keeping the rotation of the entity
+        let mut carried_rotation = None;
+        if let Some(previous_grid) = grids.find(incoming_entity)
+            && let Some(previous_state) = grids.state_mut(previous_grid)
+            && (previous_grid != grid
+                || previous_state
+                    .tracked
+                    .get(&incoming_entity)
+                    .is_some_and(|(previous_layer, _)| *previous_layer != layer))
+            && let Some((previous_layer, position, entry)) = previous_state.untrack(incoming_entity)
+        {
+            carried_rotation = Some(entry.rotation);
+            removed.send(EntityRemoved {
+                grid: previous_grid,
+                layer: previous_layer,
+                entity: incoming_entity,
+                position,
+                rotation: entry.rotation,
+            });
+        }
+
+        let origin = grids.origin(grid);
+        let Some(state) = grids.state_mut(grid) else {
+            warn!("{incoming_entity} is in {grid:?}, which has no EntityGridState");
+            return;
+        };
+        let previous_position = state
+            .tracked
+            .get(&incoming_entity)
+            .map(|(_, position)| *position);
+        // An entity staying in its cell is placed again only if its footprint changed
+        let footprint_current = match (&footprint, state.footprints.get(&incoming_entity)) {
+            (None, None) => true,
+            (Some(footprint), Some(cells)) => state.entry(incoming_entity).is_some_and(|entry| {
+                footprint
+                    .cells_at(target, entry.rotation)
+                    .eq(cells.iter().copied())
+            }),
+            _ => false,
+        };
+        if previous_position == Some(target) && footprint_current {
+            return;
+        }
+        let policy = state.settings.occupancy;
+        let rotation = match state.entry(incoming_entity).map(|entry| entry.rotation) {
+            Some(rotation) => rotation,
+            None => carried_rotation.unwrap_or_else(|| {
+                grid_rotation.map_or(state.spawn_rotation, |GridRotation(rotation)| rotation)
+            }),
+        };
+        let request = PlacementRequest::new(target)
+            .with_footprint(footprint.clone().unwrap_or_default())
+            .with_rotation(rotation)
+            .with_layer(layer)
+            .with_entity(incoming_entity);
+        let allowed = rules.check(state, &request).is_ok();
+
+        if let Some(footprint) = footprint {
+            let collisions = match state.layer(layer) {
+                Some(grid_layer) => {
+                    grid_layer.footprint_collisions(target, &footprint, rotation, incoming_entity)
+                }
+                None => Ok(Vec::new()),
+            };
+            let blocked = match collisions {
+                Err(_) => true,
+                Ok(collisions) => {
+                    let policy = if collisions
+                        .iter()
+                        .any(|occupant| is_moving(&query_common, occupant.entity))
+                    {
+                        OccupancyPolicy::Reject
+                    } else {
+                        policy
+                    };
+                    for occupant in &collisions {
+                        conflicts.send(OccupancyConflict {
+                            grid,
+                            layer,
+                            position: target,
+                            occupant: occupant.entity,
+                            incoming: incoming_entity,
+                            policy,
+                        });
+                    }
+                    let blocked =
+                        !allowed || (policy != OccupancyPolicy::Replace && !collisions.is_empty());
+                    if !blocked && policy == OccupancyPolicy::Replace {
+                        for occupant in &collisions {
+                            let Some((layer, position, entry)) = state.untrack(occupant.entity)
+                            else {
+                                continue;
+                            };
+                            commands.entity(occupant.entity).despawn_recursive();
+                            removed.send(EntityRemoved {
+                                grid,
+                                layer,
+                                entity: occupant.entity,
+                                position,
+                                rotation: entry.rotation,
+                            });
+                        }
+                    }
+                    blocked
+                }
+            };
+            if blocked {
+                // A changed footprint that doesn't fit keeps the previous cells
+                reject(
+                    &mut query_common,
+                    &mut commands,
+                    incoming_entity,
+                    previous_position,
+                );
+                return;
+            }
+
+            if let Some(previous) = previous_position {
+                state.vacate(incoming_entity, layer, previous);
+            }
+            state.insert_footprint(incoming_entity, layer, target, &footprint, rotation);
+            state.tracked.insert(incoming_entity, (layer, target));
+            place(
+                &mut query_common,
+                incoming_entity,
+                (layer, target),
+                rotation,
+                &state.settings,
+                &origin,
+            );
+            match previous_position {
+                Some(from) if from == target => {}
+                Some(from) => {
+                    moved.send(EntityMoved {
+                        grid,
+                        layer,
+                        entity: incoming_entity,
+                        from,
+                        to: target,
+                        rotation,
+                    });
+                }
+                None => {
+                    placed.send(EntityPlaced {
+                        grid,
+                        layer,
+                        entity: incoming_entity,
+                        position: target,
+                        rotation,
+                    });
+                }
+            }
+            return;
+        }
+
+        // Resolve the conflict if the cell is already occupied
+        let occupant = state
+            .get(layer, target)
+            .filter(|entry| entry.entity != incoming_entity);
+        let policy = match occupant {
+            Some(occupant) if is_moving(&query_common, occupant.entity) => OccupancyPolicy::Reject,
+            // Multi-cell occupants are never stacked or split up by a swap
+            Some(occupant)
+                if state.footprints.contains_key(&occupant.entity)
+                    && matches!(policy, OccupancyPolicy::Stack | OccupancyPolicy::Swap) =>
+            {
+                OccupancyPolicy::Reject
+            }
+            _ => policy,
+        };
+        if let Some(occupant) = occupant {
+            conflicts.send(OccupancyConflict {
+                grid,
+                layer,
+                position: target,
+                occupant: occupant.entity,
+                incoming: incoming_entity,
+                policy,
+            });
+        }
+        if !allowed || (occupant.is_some() && policy == OccupancyPolicy::Reject) {
+            reject(
+                &mut query_common,
+                &mut commands,
+                incoming_entity,
+                previous_position,
+            );
+            return;
+        }
+        if let Some(occupant) = occupant {
+            match policy {
+                OccupancyPolicy::Replace => {
+                    let position = if state.footprints.contains_key(&occupant.entity) {
+                        // A multi-cell occupant leaves every cell it covers
+                        state
+                            .untrack(occupant.entity)
+                            .map_or(target, |(_, position, _)| position)
+                    } else {
+                        // Anything stacked below the occupant stays buried
+                        state.layer_mut(layer).remove(target);
+                        state.tracked.remove(&occupant.entity);
+                        target
+                    };
+                    commands.entity(occupant.entity).despawn_recursive();
+                    removed.send(EntityRemoved {
+                        grid,
+                        layer,
+                        entity: occupant.entity,
+                        position,
+                        rotation: occupant.rotation,
+                    });
+                }
+                OccupancyPolicy::Stack => {
+                    state
+                        .stacks
+                        .entry((layer, target))
+                        .or_default()
+                        .push(occupant);
+                }
+                OccupancyPolicy::Reject | OccupancyPolicy::Swap | OccupancyPolicy::Nudge => {}
+            }
+        }
+
+        // Clear the previous cell if the entity was already tracked
+        if let Some(previous) = previous_position {
+            state.vacate(incoming_entity, layer, previous);
+        }
+
+        if let Some(occupant) = occupant {
+            match policy {
+                OccupancyPolicy::Nudge => {
+                    target = state.layer_mut(layer).nearest_free(target);
+                }
+                OccupancyPolicy::Swap => {
+                    let grid_layer = state.layer_mut(layer);
+                    let destination = match previous_position {
+                        Some(previous) if !grid_layer.contains(previous) => previous,
+                        _ => grid_layer.nearest_free(target),
+                    };
+                    grid_layer.remove(target);
+                    let _ = grid_layer.storage_mut().insert(destination, occupant);
+                    state.tracked.insert(occupant.entity, (layer, destination));
+                    place(
+                        &mut query_common,
+                        occupant.entity,
+                        (layer, destination),
+                        occupant.rotation,
+                        &state.settings,
+                        &origin,
+                    );
+                    moved.send(EntityMoved {
+                        grid,
+                        layer,
+                        entity: occupant.entity,
+                        from: target,
+                        to: destination,
+                        rotation: occupant.rotation,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        place(
+            &mut query_common,
+            incoming_entity,
+            (layer, target),
+            rotation,
+            &state.settings,
+            &origin,
+        );
+
+        // Insert the entity on top of the cell
+        state
+            .layer_mut(layer)
+            .insert(target, incoming_entity, rotation);
+        state.tracked.insert(incoming_entity, (layer, target));
+
+        match previous_position {
+            Some(from) if from == target => {}
+            Some(from) => {
+                moved.send(EntityMoved {
+                    grid,
+                    layer,
+                    entity: incoming_entity,
+                    from,
+                    to: target,
+                    rotation,
+                });
+            }
+            None => {
+                placed.send(EntityPlaced {
+                    grid,
+                    layer,
+                    entity: incoming_entity,
+                    position: target,
+                    rotation,
+                });
+            }
+        }
+    });
+}
+
+/// Send an entity back to its previous cell, or out of the grid if it wasn't placed yet
+fn reject(
+    query: &mut PlacementQuery,
+    commands: &mut Commands,
+    entity: Entity,
+    previous_position: Option<GridPosition>,
+) {
+    match previous_position {
+        Some(previous) => {
+            if let Ok((mut position, ..)) = query.get_mut(entity) {
+                position.set_if_neq(previous);
+            }
+        }
+        None => {
+            commands.entity(entity).remove::<GridPosition>();
+        }
+    }
+}
+
+/// Whether an entity is on its way to the cell it is registered in
+fn is_moving(query: &PlacementQuery, entity: Entity) -> bool {
+    query
+        .get(entity)
+        .is_ok_and(|(.., motion)| motion.is_some_and(GridMotion::is_moving))
+}
+
+/// Move an entity to the given cell and rotation
+///
+/// Both the `GridPosition` and the `Transform` are updated, relative to the origin of the grid.
+/// Entities with a `HexFacing` are rotated to face it instead of the grid rotation.
+/// Entities with a multi-cell `GridFootprint` are centred over the footprint.
+fn place(
+    query: &mut PlacementQuery,
+    entity: Entity,
+    (layer, position): (GridLayer, GridPosition),
+    rotation: Rotation,
+    settings: &EntityGridSettings,
+    origin: &Transform,
+) {
+    let Ok((mut grid_position, mut transform, .., facing, footprint, _)) = query.get_mut(entity)
+    else {
+        return;
+    };
+    grid_position.set_if_neq(position);
+    // Set the translation of the entity based on the position and layer
+    let translation = match footprint.filter(|footprint| footprint.is_multi_cell()) {
+        Some(footprint) => settings.footprint_to_world(position, footprint, rotation, layer),
+        None => settings.layer_cell_to_world(position, layer, CellAnchor::Centre),
+    };
+    // Set the rotation of the entity based on its facing or grid rotation
+    let rotation = match facing {
+        Some(HexFacing(direction)) => settings.hex_rotation(*direction),
+        None => settings.rotation(rotation),
+    };
+    let placed =
+        origin.mul_transform(Transform::from_translation(translation).with_rotation(rotation));
+    transform.translation = placed.translation;
+    transform.rotation = placed.rotation;
+}
+
+/// Apply changed `GridRotation`s to the grid and the transform of placed entities
+///
+/// Entities with a multi-cell `GridFootprint` only turn if the turned footprint is free,
+/// otherwise their `GridRotation` is set back.
+pub fn sync_grid_rotations(
+    mut changed_rotations: RotatedQuery,
+    mut rotated: EventWriter<EntityRotated>,
+    mut grids: Grids,
+) {
+    changed_rotations.iter_mut().for_each(
+        |(entity, mut grid_rotation, mut transform, has_facing, footprint)| {
+            let rotation = grid_rotation.0;
+            let Some(grid) = grids.find(entity) else {
+                return;
+            };
+            let origin = grids.origin(grid);
+            let Some(state) = grids.state_mut(grid) else {
+                return;
+            };
+            let Some((layer, position)) = state.tracked.get(&entity).copied() else {
+                return;
+            };
+            let Some(from) = state.entry(entity).map(|entry| entry.rotation) else {
+                return;
+            };
+            if from == rotation {
+                return;
+            }
+            match footprint.filter(|footprint| footprint.is_multi_cell()) {
+                Some(footprint) => {
+                    let fits = state.layer(layer).is_some_and(|grid_layer| {
+                        grid_layer.footprint_fits(position, footprint, rotation, entity)
+                    });
+                    if !fits {
+                        grid_rotation.0 = from;
+                        return;
+                    }
+                    // Turning the footprint moves its cells and its centre
+                    state.insert_footprint(entity, layer, position, footprint, rotation);
+                    let centre = state
+                        .settings
+                        .footprint_to_world(position, footprint, rotation, layer);
+                    transform.translation = origin.transform_point(centre);
+                }
+                None => {
+                    if let Some(entry) = state.entry_mut(entity) {
+                        entry.rotation = rotation;
+                    }
+                }
+            }
+            if !has_facing {
+                transform.rotation = origin.rotation * state.settings.rotation(rotation);
+            }
+            rotated.send(EntityRotated {
+                grid,
+                layer,
+                entity,
+                position,
+                from,
+                to: rotation,
+            });
+        },
+    );
+}
+
+/// Apply changed `HexFacing`s to the transform of placed entities
+pub fn sync_hex_facings(mut changed_facings: FacingQuery, grids: Grids) {
+    changed_facings
+        .iter_mut()
+        .for_each(|(entity, &HexFacing(direction), mut transform)| {
+            let Some(grid) = grids.find(entity) else {
+                return;
+            };
+            let Some(state) = grids.state(grid) else {
+                return;
+            };
+            transform.rotation =
+                grids.origin(grid).rotation * state.settings.hex_rotation(direction);
+        });
+}
+
+/// The placed entities whose tags may be missing from their cells
+type TaggedQuery<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, Option<&'static GridTags>),
+    (
+        With<GridPosition>,
+        Or<(
+            Changed<GridTags>,
+            Changed<GridPosition>,
+            Changed<GridRotation>,
+            Changed<InGrid>,
+            Changed<GridLayer>,
+            Changed<GridFootprint>,
+        )>,
+    ),
+>;
+
+/// Copy the `GridTags` of placed entities to their cells
+///
+/// Tags are written again whenever an entity is placed, moved or turned, and cleared when the
+/// component is removed.
+pub fn sync_grid_tags(
+    tagged: TaggedQuery,
+    mut removed_tags: RemovedComponents<GridTags>,
+    mut grids: Grids,
+) {
+    let untagged = removed_tags.read().map(|entity| (entity, None));
+    for (entity, tags) in untagged.chain(&tagged) {
+        let Some(grid) = grids.find(entity) else {
+            continue;
+        };
+        if let Some(state) = grids.state_mut(grid) {
+            state.set_tags(entity, tags.copied().unwrap_or_default());
+        }
+    }
+}
+
+/// Place the entities that lost their `InGrid`, `GridLayer` or `GridFootprint` again
+///
+/// Removing a component doesn't trigger `Changed`, so their position is marked as changed for
+/// `sync_grid_positions` to move them to the default grid or layer, or to a single cell.
+pub fn requeue_removed_placements(
+    mut removed_grids: RemovedComponents<InGrid>,
+    mut removed_layers: RemovedComponents<GridLayer>,
+    mut removed_footprints: RemovedComponents<GridFootprint>,
+    mut positions: Query<&mut GridPosition>,
+) {
+    for entity in removed_grids
+        .read()
+        .chain(removed_layers.read())
+        .chain(removed_footprints.read())
+    {
+        if let Ok(mut position) = positions.get_mut(entity) {
+            position.set_changed();
+        }
+    }
+}
+
+/// Clear the cells of entities that lost their position or were despawned
+pub fn clear_removed_positions(
+    mut removed_positions: RemovedComponents<GridPosition>,
+    mut removed: EventWriter<EntityRemoved>,
+    mut grids: Grids,
+) {
+    removed_positions.read().for_each(|entity| {
+        let Some(grid) = grids.find(entity) else {
+            return;
+        };
+        let Some(state) = grids.state_mut(grid) else {
+            return;
+        };
+        // Only clear the cell if it still holds this entity
+        if let Some((layer, position, entry)) = state.untrack(entity) {
+            removed.send(EntityRemoved {
+                grid,
+                layer,
+                entity,
+                position,
+                rotation: entry.rotation,
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(app: &App) -> &EntityGridState {
+        app.world().get_resource::<EntityGridState>().unwrap()
+    }
+
+    fn set_policy(app: &mut App, policy: OccupancyPolicy) {
+        app.world_mut()
+            .get_resource_mut::<EntityGridState>()
+            .unwrap()
+            .settings
+            .occupancy = policy;
+    }
+
+    fn spawn_at(app: &mut App, position: GridPosition) -> Entity {
+        let entity = app.world_mut().spawn((Transform::default(), position)).id();
+        app.update();
+        entity
+    }
+
+    fn occupant(app: &App, position: GridPosition) -> Option<Entity> {
+        state(app).grid.get(position).map(|entry| entry.entity)
+    }
+
+    fn events<E: Event + Clone>(app: &App) -> Vec<E> {
+        let events = app.world().resource::<Events<E>>();
+        events.get_cursor().read(events).cloned().collect()
+    }
+
+    #[test]
+    fn test_move_entity() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        app.world_mut()
+            .get_resource_mut::<EntityGridState>()
+            .unwrap()
+            .spawn_rotation = Rotation::Left;
+        let entity = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(0, 0)))
+            .id();
+        app.update();
+
+        *app.world_mut().get_mut::<GridPosition>(entity).unwrap() = GridPosition::new(2, 3);
+        app.update();
+
+        let grid = &state(&app).grid;
+        assert_eq!(grid.get(GridPosition::new(0, 0)), None);
+        assert_eq!(
+            grid.get(GridPosition::new(2, 3)),
+            Some(GridEntity::new(entity, Rotation::Left))
+        );
+        assert_eq!(grid.len(), 1);
+        assert_eq!(
+            app.world().get::<Transform>(entity).unwrap().translation,
+            Vec3::new(2.0, 0.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn test_remove_position() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        let entity = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(1, 1)))
+            .id();
+        app.update();
+
+        app.world_mut().entity_mut(entity).remove::<GridPosition>();
+        app.update();
+
+        assert_eq!(state(&app).grid.len(), 0);
+        assert!(state(&app).tracked.is_empty());
+    }
+
+    #[test]
+    fn test_despawn_entity() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        let entity = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(1, 1)))
+            .id();
+        app.update();
+
+        app.world_mut().despawn(entity);
+        app.update();
+
+        assert_eq!(state(&app).grid.get(GridPosition::new(1, 1)), None);
+        assert!(state(&app).tracked.is_empty());
+    }
+
+    #[test]
+    fn test_stale_cell_is_kept() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        set_policy(&mut app, OccupancyPolicy::Stack);
+        let first = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(0, 0)))
+            .id();
+        app.update();
+        // A second entity is stacked on top of the first one
+        let second = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(0, 0)))
+            .id();
+        app.update();
+
+        // Moving and despawning the first entity must not clear the second
+        *app.world_mut().get_mut::<GridPosition>(first).unwrap() = GridPosition::new(5, 5);
+        app.update();
+        assert_eq!(
+            state(&app)
+                .grid
+                .get(GridPosition::new(0, 0))
+                .map(|entry| entry.entity),
+            Some(second)
+        );
+
+        app.world_mut().despawn(first);
+        app.update();
+        assert_eq!(
+            state(&app)
+                .grid
+                .get(GridPosition::new(0, 0))
+                .map(|entry| entry.entity),
+            Some(second)
+        );
+        assert_eq!(state(&app).grid.get(GridPosition::new(5, 5)), None);
+    }
+
+    #[test]
+    fn test_policy_replace() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        let first = spawn_at(&mut app, GridPosition::new(0, 0));
+        let second = spawn_at(&mut app, GridPosition::new(0, 0));
+
+        assert_eq!(occupant(&app, GridPosition::new(0, 0)), Some(second));
+        assert!(app.world().get_entity(first).is_err());
+        assert_eq!(
+            events::<OccupancyConflict>(&app),
+            vec![OccupancyConflict {
+                grid: None,
+                layer: GridLayer::DEFAULT,
+                position: GridPosition::new(0, 0),
+                occupant: first,
+                incoming: second,
+                policy: OccupancyPolicy::Replace,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_policy_reject() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        set_policy(&mut app, OccupancyPolicy::Reject);
+        let first = spawn_at(&mut app, GridPosition::new(0, 0));
+        let second = spawn_at(&mut app, GridPosition::new(0, 0));
+
+        assert_eq!(occupant(&app, GridPosition::new(0, 0)), Some(first));
+        assert_eq!(app.world().get::<GridPosition>(second), None);
+
+        // A rejected move is sent back to its previous cell
+        let third = spawn_at(&mut app, GridPosition::new(1, 0));
+        *app.world_mut().get_mut::<GridPosition>(third).unwrap() = GridPosition::new(0, 0);
+        app.update();
+        app.update();
+
+        assert_eq!(occupant(&app, GridPosition::new(0, 0)), Some(first));
+        assert_eq!(occupant(&app, GridPosition::new(1, 0)), Some(third));
+        assert_eq!(
+            app.world().get::<GridPosition>(third),
+            Some(&GridPosition::new(1, 0))
+        );
+        assert_eq!(state(&app).grid.len(), 2);
+    }
+
+    #[test]
+    fn test_policy_stack() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        set_policy(&mut app, OccupancyPolicy::Stack);
+        let first = spawn_at(&mut app, GridPosition::new(0, 0));
+        let second = spawn_at(&mut app, GridPosition::new(0, 0));
+
+        assert_eq!(occupant(&app, GridPosition::new(0, 0)), Some(second));
+
+        // The buried entity is restored once the top one leaves
+        app.world_mut().despawn(second);
+        app.update();
+
+        assert_eq!(occupant(&app, GridPosition::new(0, 0)), Some(first));
+        assert!(state(&app).stacks.is_empty());
+    }
+
+    #[test]
+    fn test_policy_swap() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        set_policy(&mut app, OccupancyPolicy::Swap);
+        let first = spawn_at(&mut app, GridPosition::new(0, 0));
+        let second = spawn_at(&mut app, GridPosition::new(3, 0));
+
+        *app.world_mut().get_mut::<GridPosition>(second).unwrap() = GridPosition::new(0, 0);
+        app.update();
+        app.update();
+
+        assert_eq!(occupant(&app, GridPosition::new(0, 0)), Some(second));
+        assert_eq!(occupant(&app, GridPosition::new(3, 0)), Some(first));
+        assert_eq!(
+            app.world().get::<GridPosition>(first),
+            Some(&GridPosition::new(3, 0))
+        );
+        assert_eq!(
+            app.world().get::<Transform>(first).unwrap().translation,
+            Vec3::new(3.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_policy_nudge() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        set_policy(&mut app, OccupancyPolicy::Nudge);
+        let first = spawn_at(&mut app, GridPosition::new(0, 0));
+        let second = spawn_at(&mut app, GridPosition::new(0, 0));
+        app.update();
+
+        assert_eq!(occupant(&app, GridPosition::new(0, 0)), Some(first));
+        let nudged = *app.world().get::<GridPosition>(second).unwrap();
+        assert_ne!(nudged, GridPosition::new(0, 0));
+        assert_eq!(occupant(&app, nudged), Some(second));
+        assert_eq!(state(&app).grid.len(), 2);
+    }
+
+    #[test]
+    fn test_lifecycle_events() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        let entity = spawn_at(&mut app, GridPosition::new(0, 0));
+        assert_eq!(
+            events::<EntityPlaced>(&app),
+            vec![EntityPlaced {
+                grid: None,
+                layer: GridLayer::DEFAULT,
+                entity,
+                position: GridPosition::new(0, 0),
+                rotation: Rotation::Up,
+            }]
+        );
+
+        *app.world_mut().get_mut::<GridPosition>(entity).unwrap() = GridPosition::new(1, 0);
+        app.update();
+        assert_eq!(
+            events::<EntityMoved>(&app),
+            vec![EntityMoved {
+                grid: None,
+                layer: GridLayer::DEFAULT,
+                entity,
+                from: GridPosition::new(0, 0),
+                to: GridPosition::new(1, 0),
+                rotation: Rotation::Up,
+            }]
+        );
+
+        app.world_mut().despawn(entity);
+        app.update();
+        assert_eq!(
+            events::<EntityRemoved>(&app),
+            vec![EntityRemoved {
+                grid: None,
+                layer: GridLayer::DEFAULT,
+                entity,
+                position: GridPosition::new(1, 0),
+                rotation: Rotation::Up,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_grid_rotation() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        let entity = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GridPosition::new(0, 0),
+                GridRotation(Rotation::Right),
+            ))
+            .id();
+        let fallback = spawn_at(&mut app, GridPosition::new(1, 0));
+
+        assert_eq!(
+            state(&app).grid.get(GridPosition::new(0, 0)),
+            Some(GridEntity::new(entity, Rotation::Right))
+        );
+        assert_eq!(
+            state(&app).grid.get(GridPosition::new(1, 0)),
+            Some(GridEntity::new(fallback, Rotation::Up))
+        );
+        assert!(events::<EntityRotated>(&app).is_empty());
+
+        app.world_mut().get_mut::<GridRotation>(entity).unwrap().0 = Rotation::Down;
+        app.update();
+
+        assert_eq!(
+            state(&app).grid.get(GridPosition::new(0, 0)),
+            Some(GridEntity::new(entity, Rotation::Down))
+        );
+        assert_eq!(
+            app.world().get::<Transform>(entity).unwrap().rotation,
+            Quat::from_rotation_y(Rotation::Down.to_angle())
+        );
+        assert_eq!(
+            events::<EntityRotated>(&app),
+            vec![EntityRotated {
+                grid: None,
+                layer: GridLayer::DEFAULT,
+                entity,
+                position: GridPosition::new(0, 0),
+                from: Rotation::Right,
+                to: Rotation::Down,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_hex_placement() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        app.world_mut()
+            .resource_mut::<EntityGridState>()
+            .settings
+            .lattice = GridLattice::Hex(HexOrientation::Flat);
+        let entity = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GridPosition::new(2, -1),
+                HexFacing(HexDirection::NorthEast),
+            ))
+            .id();
+        app.update();
+
+        let transform = *app.world().get::<Transform>(entity).unwrap();
+        let settings = &state(&app).settings;
+        assert!(
+            transform
+                .translation
+                .abs_diff_eq(Vec3::new(3.0, 0.0, 0.0), 1e-5)
+        );
+        assert_eq!(
+            transform.rotation,
+            settings.hex_rotation(HexDirection::NorthEast)
+        );
+
+        app.world_mut().get_mut::<HexFacing>(entity).unwrap().0 = HexDirection::West;
+        app.update();
+        let forward = app.world().get::<Transform>(entity).unwrap().rotation * Vec3::Z;
+        assert!(forward.abs_diff_eq(Vec3::new(-1.5, 0.0, -0.866_025_4).normalize(), 1e-5));
+    }
+
+    #[test]
+    fn test_footprint_placement() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        set_policy(&mut app, OccupancyPolicy::Reject);
+        let rock = spawn_at(&mut app, GridPosition::new(3, 1));
+        let building = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GridPosition::new(0, 0),
+                GridFootprint::rectangle(2, 3),
+            ))
+            .id();
+        app.update();
+
+        assert_eq!(state(&app).grid.len(), 7);
+        assert_eq!(occupant(&app, GridPosition::new(1, 2)), Some(building));
+        assert_eq!(
+            app.world().get::<Transform>(building).unwrap().translation,
+            Vec3::new(0.5, 0.0, 1.0)
+        );
+        // The building is a single neighbor even though it covers several cells
+        let neighbors =
+            state(&app).get_square_radius_neighbors(GridPosition::new(2, 1), 1, GridLayer::DEFAULT);
+        assert_eq!(neighbors.neighbors.len(), 2);
+
+        // Moving onto the rock is rejected
+        *app.world_mut().get_mut::<GridPosition>(building).unwrap() = GridPosition::new(2, 0);
+        app.update();
+        app.update();
+        assert_eq!(
+            *app.world().get::<GridPosition>(building).unwrap(),
+            GridPosition::new(0, 0)
+        );
+        assert_eq!(events::<OccupancyConflict>(&app).len(), 1);
+
+        // Turning right covers the cells below the anchor
+        app.world_mut()
+            .entity_mut(building)
+            .insert(GridRotation(Rotation::Right));
+        app.update();
+        assert_eq!(occupant(&app, GridPosition::new(2, -1)), Some(building));
+        assert_eq!(occupant(&app, GridPosition::new(0, 1)), None);
+        assert_eq!(state(&app).grid.len(), 7);
+
+        app.world_mut().despawn(building);
+        app.update();
+        assert_eq!(state(&app).grid.len(), 1);
+        assert_eq!(occupant(&app, GridPosition::new(3, 1)), Some(rock));
+    }
+
+    #[test]
+    fn test_enter_footprint() {
+        for policy in [
+            OccupancyPolicy::Replace,
+            OccupancyPolicy::Reject,
+            OccupancyPolicy::Stack,
+            OccupancyPolicy::Swap,
+            OccupancyPolicy::Nudge,
+        ] {
+            let mut app = App::new();
+            setup_plugin(&mut app);
+            set_policy(&mut app, policy);
+            let building = app
+                .world_mut()
+                .spawn((
+                    Transform::default(),
+                    GridPosition::new(0, 0),
+                    GridFootprint::rectangle(2, 2),
+                ))
+                .id();
+            app.update();
+            let unit = spawn_at(&mut app, GridPosition::new(1, 1));
+            let state = state(&app);
+            assert!(state.stacks.is_empty(), "{policy:?}");
+
+            match policy {
+                OccupancyPolicy::Replace => {
+                    // The whole building is gone, not only the entered cell
+                    assert!(app.world().get_entity(building).is_err());
+                    assert_eq!(state.grid.len(), 1, "{policy:?}");
+                    assert!(state.footprints.is_empty());
+                    assert_eq!(occupant(&app, GridPosition::new(1, 1)), Some(unit));
+                }
+                OccupancyPolicy::Nudge => {
+                    // The unit moves aside, leaving the building whole
+                    let position = *app.world().get::<GridPosition>(unit).unwrap();
+                    assert_eq!(occupant(&app, position), Some(unit));
+                    assert_eq!(state.grid.len(), 5);
+                    assert_eq!(state.footprints[&building].len(), 4);
+                }
+                _ => {
+                    // Stacking on or swapping a building is rejected
+                    assert!(
+                        app.world().get::<GridPosition>(unit).is_none(),
+                        "{policy:?}"
+                    );
+                    assert_eq!(state.grid.len(), 4, "{policy:?}");
+                    assert_eq!(occupant(&app, GridPosition::new(1, 1)), Some(building));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_footprint_changes() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        let wall = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GridPosition::new(0, 0),
+                GridFootprint::rectangle(3, 1),
+            ))
+            .id();
+        app.update();
+
+        // The wall is a single ordinal neighbor even though it covers two of them
+        let neighbors =
+            state(&app).get_ordinal_neighbors(GridPosition::new(1, 1), GridLayer::DEFAULT);
+        assert_eq!(
+            neighbors.south_east.map(|neighbor| neighbor.entry.entity),
+            Some(wall)
+        );
+        assert_eq!(neighbors.south_west, None);
+
+        // Shrinking the footprint frees the cells it no longer covers
+        app.world_mut()
+            .entity_mut(wall)
+            .insert(GridFootprint::rectangle(2, 1));
+        app.update();
+        assert_eq!(state(&app).grid.len(), 2);
+        assert_eq!(occupant(&app, GridPosition::new(2, 0)), None);
+        assert_eq!(state(&app).footprints.get(&wall).map(Vec::len), Some(2));
+
+        // Removing the footprint leaves the anchor cell only
+        app.world_mut().entity_mut(wall).remove::<GridFootprint>();
+        app.update();
+        assert_eq!(state(&app).grid.len(), 1);
+        assert_eq!(occupant(&app, GridPosition::new(0, 0)), Some(wall));
+        assert!(state(&app).footprints.is_empty());
+        assert_eq!(
+            app.world().get::<Transform>(wall).unwrap().translation,
+            Vec3::ZERO
+        );
+        // The wall never left its anchor cell
+        assert!(events::<EntityMoved>(&app).is_empty());
+    }
+
+    #[test]
+    fn test_multiple_grids() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        let grid = app
+            .world_mut()
+            .spawn((
+                EntityGridState::new(EntityGridSettings {
+                    cell_size: 2.0,
+                    ..default()
+                }),
+                Transform::from_xyz(10.0, 0.0, 0.0),
+            ))
+            .id();
+        let default_entity = spawn_at(&mut app, GridPosition::new(1, 1));
+        let entity = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(1, 1), InGrid(grid)))
+            .id();
+        app.update();
+
+        let grid_state = app.world().get::<EntityGridState>(grid).unwrap();
+        assert_eq!(
+            grid_state
+                .grid
+                .get(GridPosition::new(1, 1))
+                .map(|entry| entry.entity),
+            Some(entity)
+        );
+        assert_eq!(
+            occupant(&app, GridPosition::new(1, 1)),
+            Some(default_entity)
+        );
+        assert_eq!(
+            app.world().get::<Transform>(entity).unwrap().translation,
+            Vec3::new(12.0, 0.0, 2.0)
+        );
+
+        // Moving the entity to the default grid removes it from the grid entity
+        app.world_mut().entity_mut(entity).remove::<InGrid>();
+        *app.world_mut().get_mut::<GridPosition>(entity).unwrap() = GridPosition::new(2, 2);
+        app.update();
+
+        let grid_state = app.world().get::<EntityGridState>(grid).unwrap();
+        assert!(grid_state.grid.is_empty());
+        assert_eq!(occupant(&app, GridPosition::new(2, 2)), Some(entity));
+        assert_eq!(
+            events::<EntityRemoved>(&app),
+            vec![EntityRemoved {
+                grid: Some(grid),
+                layer: GridLayer::DEFAULT,
+                entity,
+                position: GridPosition::new(1, 1),
+                rotation: Rotation::Up,
+            }]
+        );
+
+        // Despawning the entity clears the cell of the grid it is in
+        app.world_mut().despawn(entity);
+        app.update();
+        assert_eq!(occupant(&app, GridPosition::new(2, 2)), None);
+    }
+
+    #[test]
+    fn test_remove_in_grid() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        let grid = app
+            .world_mut()
+            .spawn((EntityGridState::new(EntityGridSettings::default()),))
+            .id();
+        let entity = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(1, 1), InGrid(grid)))
+            .id();
+        app.update();
+
+        // Removing only the InGrid returns the entity to the default grid, in the same cell
+        app.world_mut().entity_mut(entity).remove::<InGrid>();
+        app.update();
+        let grid_state = app.world().get::<EntityGridState>(grid).unwrap();
+        assert!(grid_state.grid.is_empty());
+        assert!(!grid_state.tracked.contains_key(&entity));
+        assert_eq!(occupant(&app, GridPosition::new(1, 1)), Some(entity));
+        assert_eq!(
+            state(&app).tracked.get(&entity),
+            Some(&(GridLayer::DEFAULT, GridPosition::new(1, 1)))
+        );
+    }
+
+    #[test]
+    fn test_layers() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        let floor = GridLayer(1);
+        app.world_mut()
+            .get_resource_mut::<EntityGridState>()
+            .unwrap()
+            .settings
+            .layer_offsets
+            .insert(floor, -1.0);
+
+        let building = spawn_at(&mut app, GridPosition::new(0, 0));
+        let tile = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(0, 0), floor))
+            .id();
+        app.update();
+
+        // Both entities share the cell on their own layer
+        assert!(app.world().get_entity(building).is_ok());
+        assert!(events::<OccupancyConflict>(&app).is_empty());
+        assert_eq!(occupant(&app, GridPosition::new(0, 0)), Some(building));
+        assert_eq!(
+            state(&app)
+                .get(floor, GridPosition::new(0, 0))
+                .map(|entry| entry.entity),
+            Some(tile)
+        );
+        assert_eq!(
+            app.world().get::<Transform>(tile).unwrap().translation,
+            Vec3::new(0.0, -1.0, 0.0)
+        );
+
+        // Changing layer moves the entity to the other occupancy map
+        app.world_mut().entity_mut(tile).insert(GridLayer(2));
+        app.update();
+        assert!(state(&app).layer(floor).unwrap().is_empty());
+        assert_eq!(
+            state(&app)
+                .get(GridLayer(2), GridPosition::new(0, 0))
+                .map(|entry| entry.entity),
+            Some(tile)
+        );
+
+        // Removing only the layer returns the entity to the default layer and height
+        app.world_mut().despawn(building);
+        app.world_mut().entity_mut(tile).remove::<GridLayer>();
+        app.update();
+        assert!(state(&app).layer(GridLayer(2)).unwrap().is_empty());
+        assert_eq!(
+            state(&app).tracked.get(&tile),
+            Some(&(GridLayer::DEFAULT, GridPosition::new(0, 0)))
+        );
+        assert_eq!(
+            app.world().get::<Transform>(tile).unwrap().translation,
+            Vec3::ZERO
+        );
+    }
+
+    #[test]
+    fn test_grid_tags() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        let wall = GridTags::bit(0);
+        let tags_at = |app: &App, position| state(app).grid.get(position).map(|entry| entry.tags);
+        let building = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                GridPosition::new(0, 0),
+                GridFootprint::rectangle(2, 1),
+                wall,
+            ))
+            .id();
+        let unit = spawn_at(&mut app, GridPosition::new(0, 3));
+        assert_eq!(tags_at(&app, GridPosition::new(1, 0)), Some(wall));
+        assert_eq!(tags_at(&app, GridPosition::new(0, 3)), Some(GridTags::NONE));
+
+        // Moving and turning keep the tags of every cell
+        *app.world_mut().get_mut::<GridPosition>(building).unwrap() = GridPosition::new(4, 0);
+        app.world_mut()
+            .entity_mut(building)
+            .insert(GridRotation(Rotation::Right));
+        app.update();
+        assert_eq!(tags_at(&app, GridPosition::new(4, -1)), Some(wall));
+        assert_eq!(tags_at(&app, GridPosition::new(0, 0)), None);
+
+        app.world_mut().entity_mut(unit).insert(GridTags::bit(1));
+        app.world_mut().entity_mut(building).remove::<GridTags>();
+        app.update();
+        assert_eq!(
+            tags_at(&app, GridPosition::new(0, 3)),
+            Some(GridTags::bit(1))
+        );
+        assert_eq!(tags_at(&app, GridPosition::new(4, 0)), Some(GridTags::NONE));
+    }
+
+    #[test]
+    fn test_stacked_tags() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        set_policy(&mut app, OccupancyPolicy::Stack);
+        let floor = GridTags::bit(2);
+        let tile = app
+            .world_mut()
+            .spawn((Transform::default(), GridPosition::new(0, 0), floor))
+            .id();
+        app.update();
+        let cargo = spawn_at(&mut app, GridPosition::new(0, 0));
+        assert_eq!(occupant(&app, GridPosition::new(0, 0)), Some(cargo));
+
+        // The buried tile comes back with its tags when the cargo leaves
+        *app.world_mut().get_mut::<GridPosition>(cargo).unwrap() = GridPosition::new(1, 0);
+        app.update();
+        assert_eq!(
+            state(&app).grid.get(GridPosition::new(0, 0)),
+            Some(GridEntity {
+                tags: floor,
+                ..GridEntity::new(tile, Rotation::Up)
+            })
+        );
+    }
+}
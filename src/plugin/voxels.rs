@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+
+use crate::prelude::*;
+
+/// The voxel entities whose position or grid changed
+type VoxelQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut VoxelPosition,
+        &'static mut Transform,
+        Option<&'static GridRotation>,
+        Option<&'static InGrid>,
+    ),
+    Or<(Changed<VoxelPosition>, Changed<InGrid>)>,
+>;
+
+/// The placed voxel entities whose rotation changed
+type VoxelRotatedQuery<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static GridRotation, &'static mut Transform),
+    (With<VoxelPosition>, Changed<GridRotation>),
+>;
+
+/// Register added or moved voxel entities in the `VoxelGrid` of their grid
+///
+/// Entities placed on an occupied voxel are stacked on top of it, at the first free level above.
+/// Their `VoxelPosition` is updated to the level they end up on.
+///
+/// Voxels are deliberately kept minimal: they don't send placement events, always stack instead
+/// of following the `OccupancyPolicy`, and have no layers or tags.
+pub fn sync_voxel_positions(mut voxels: VoxelQuery, mut grids: Grids) {
+    voxels.iter_mut().for_each(
+        |(entity, mut position, mut transform, grid_rotation, in_grid)| {
+            let grid = in_grid.map(|InGrid(grid)| *grid);
+
+            // Leave the previous voxel, keeping the rotation of the entity
+            let mut carried_rotation = None;
+            if let Some(previous_grid) = grids.find_voxel(entity)
+                && let Some(previous_state) = grids.state_mut(previous_grid)
+            {
+                if previous_grid == grid
+                    && previous_state.voxel_tracked.get(&entity) == Some(&*position)
+                {
+                    return;
+                }
+                carried_rotation = previous_state
+                    .untrack_voxel(entity)
+                    .map(|(_, entry)| entry.rotation);
+            }
+
+            let origin = grids.origin(grid);
+            let Some(state) = grids.state_mut(grid) else {
+                warn!("{entity} is in {grid:?}, which has no EntityGridState");
+                return;
+            };
+            let rotation = carried_rotation
+                .or(grid_rotation.map(|GridRotation(rotation)| *rotation))
+                .unwrap_or(state.spawn_rotation);
+
+            let target = state.voxels.first_free_above(*position);
+            if state.voxels.insert(target, entity, rotation).is_err() {
+                return;
+            }
+            state.voxel_tracked.insert(entity, target);
+            position.set_if_neq(target);
+
+            let translation = state.settings.voxel_to_world(target, CellAnchor::Centre);
+            let rotation = state.settings.rotation(rotation);
+            let placed = origin
+                .mul_transform(Transform::from_translation(translation).with_rotation(rotation));
+            transform.translation = placed.translation;
+            transform.rotation = placed.rotation;
+        },
+    );
+}
+
+/// Apply changed `GridRotation`s to the voxel and the transform of placed voxel entities
+pub fn sync_voxel_rotations(mut rotated: VoxelRotatedQuery, mut grids: Grids) {
+    rotated
+        .iter_mut()
+        .for_each(|(entity, &GridRotation(rotation), mut transform)| {
+            let Some(grid) = grids.find_voxel(entity) else {
+                return;
+            };
+            let origin = grids.origin(grid);
+            let Some(state) = grids.state_mut(grid) else {
+                return;
+            };
+            let Some(position) = state.voxel_tracked.get(&entity).copied() else {
+                return;
+            };
+            if let Some(entry) = state.voxels.get_mut(position) {
+                entry.rotation = rotation;
+            }
+            transform.rotation = origin.rotation * state.settings.rotation(rotation);
+        });
+}
+
+/// Clear the voxels of entities that lost their position or were despawned
+pub fn clear_removed_voxels(
+    mut removed_positions: RemovedComponents<VoxelPosition>,
+    mut grids: Grids,
+) {
+    removed_positions.read().for_each(|entity| {
+        let Some(grid) = grids.find_voxel(entity) else {
+            return;
+        };
+        if let Some(state) = grids.state_mut(grid) {
+            state.untrack_voxel(entity);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn_at(app: &mut App, position: VoxelPosition) -> Entity {
+        let entity = app.world_mut().spawn((Transform::default(), position)).id();
+        app.update();
+        entity
+    }
+
+    #[test]
+    fn test_voxel_stacking() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+        app.world_mut()
+            .resource_mut::<EntityGridState>()
+            .settings
+            .level_height = 2.0;
+
+        let base = spawn_at(&mut app, VoxelPosition::new(1, 1, 0));
+        let top = spawn_at(&mut app, VoxelPosition::new(1, 1, 0));
+        // The second entity is stacked on top of the first
+        assert_eq!(
+            *app.world().get::<VoxelPosition>(top).unwrap(),
+            VoxelPosition::new(1, 1, 1)
+        );
+        assert_eq!(
+            app.world().get::<Transform>(top).unwrap().translation,
+            Vec3::new(1.0, 2.0, 1.0)
+        );
+        let state = app.world().resource::<EntityGridState>();
+        assert_eq!(state.voxels.top(GridPosition::new(1, 1)), Some(1));
+        assert_eq!(
+            state
+                .voxels
+                .get(VoxelPosition::new(1, 1, 0))
+                .map(|entry| entry.entity),
+            Some(base)
+        );
+
+        // Moving the base frees its voxel
+        *app.world_mut().get_mut::<VoxelPosition>(base).unwrap() = VoxelPosition::new(3, 1, 0);
+        app.update();
+        app.world_mut().despawn(top);
+        app.update();
+        let state = app.world().resource::<EntityGridState>();
+        assert_eq!(state.voxels.len(), 1);
+        assert!(state.voxels.contains(VoxelPosition::new(3, 1, 0)));
+        assert!(state.voxel_tracked.get(&top).is_none());
+    }
+
+    #[test]
+    fn test_voxel_rotation() {
+        let mut app = App::new();
+        setup_plugin(&mut app);
+
+        let entity = spawn_at(&mut app, VoxelPosition::new(0, 0, 0));
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(GridRotation(Rotation::Right));
+        app.update();
+
+        let state = app.world().resource::<EntityGridState>();
+        assert_eq!(
+            state
+                .voxels
+                .get(VoxelPosition::new(0, 0, 0))
+                .map(|entry| entry.rotation),
+            Some(Rotation::Right)
+        );
+        assert_eq!(
+            app.world().get::<Transform>(entity).unwrap().rotation,
+            state.settings.rotation(Rotation::Right)
+        );
+    }
+}